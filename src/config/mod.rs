@@ -1,64 +1,629 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentConfig;
 use std::env;
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::utils::error::{ScannerError, ScannerResult};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct AppConfig {
-    pub solana_rpc_url: String,
+    pub solana_rpc_urls: Vec<String>,
+    pub solana_ws_url: String,
+    pub scan_mode: ScanMode,
+    pub log_format: LogFormat,
+    pub commitment: Commitment,
     pub mongodb_uri: String,
+    /// 连接池最大/最小连接数，未设置时使用驱动默认值
+    pub mongo_max_pool_size: Option<u32>,
+    pub mongo_min_pool_size: Option<u32>,
+    pub mongo_connect_timeout_ms: u64,
+    /// 启动时连接 MongoDB 失败的重试次数
+    pub mongo_connect_retries: u32,
+    /// 交易数据保留天数，设置后在 timestamp 上创建 TTL 索引自动过期
+    pub transaction_ttl_days: Option<u64>,
+    /// 只扫描和解析，不写入 MongoDB、不发送 Kafka/WebSocket，检查点也不落库
+    pub dry_run: bool,
     pub kafka_config: KafkaConfig,
+    /// RPC 与 WebSocket 服务监听的 IP 地址
+    pub bind_host: String,
     pub rpc_port: u16,
     pub websocket_port: u16,
+    /// 单端口模式：WebSocket 的 /ws 与 RPC 接口一起在 rpc_port 上提供，websocket_port 不再使用
+    pub single_port: bool,
+    /// WebSocket 连接鉴权 token，未设置时不鉴权
+    pub ws_auth_token: Option<String>,
+    /// PEM 格式的证书与私钥路径，两者都设置时 RPC 与 WebSocket 服务启用 TLS
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    /// WebSocket 最大连接数，超过后拒绝升级
+    pub ws_max_connections: usize,
+    /// 每个 WebSocket 连接发送队列的容量，队列满时断开该连接
+    pub ws_channel_capacity: usize,
+    /// 交易 webhook 地址，未设置时不启用
+    pub webhook_url: Option<String>,
+    /// 请求体 HMAC-SHA256 签名密钥，未设置时不签名
+    pub webhook_secret: Option<String>,
+    pub webhook_timeout_ms: u64,
+    /// 网络错误、429 或 5xx 时的重试次数
+    pub webhook_max_retries: u32,
     pub scan_interval_secs: u64,
+    /// 毫秒级扫描间隔，设置后覆盖 scan_interval_secs
+    pub scan_interval_ms: Option<u64>,
     pub max_addresses: usize,
     pub max_concurrent_requests: usize,
+    /// 每轮扫描最多处理的 slot 数，落后较多时分多轮追赶
+    pub max_slots_per_tick: u64,
+    pub reorg_depth: u64,
+    /// 没有检查点时从最新 slot 往回扫描的 slot 数
+    pub initial_lookback_slots: u64,
+    /// 没有检查点时从指定 slot 开始扫描，优先于 initial_lookback_slots；已有检查点时忽略
+    pub start_slot: Option<u64>,
+    /// 多实例分片扫描：本实例只处理 slot % shard_count == shard_index 的 slot
+    pub shard_index: u64,
+    pub shard_count: u64,
+    /// 多实例高可用：通过 MongoDB 租约选主，只有 leader 执行正向扫描
+    pub leader_election: bool,
+    /// 租约有效期，leader 每三分之一周期续期一次
+    pub leader_lease_secs: u64,
+    /// 租约中记录的实例标识，未设置时使用主机名加随机后缀
+    pub instance_id: Option<String>,
+    /// 缓存最近区块哈希和出块时间的 slot 数
+    pub block_cache_size: usize,
+    pub max_retries: u32,
+    pub retry_base_delay_ms: u64,
+    /// 单次 RPC 请求超时，超时按瞬时错误重试
+    pub rpc_timeout_secs: u64,
+    /// 所有 RPC 请求的限流速率，0 表示不限流
+    pub rpc_requests_per_second: u32,
+    pub missed_slot_retry_secs: u64,
+    pub token_metadata_ttl_secs: u64,
+    pub address_reload_secs: u64,
+    /// 扫描落后超过该 slot 数时 /health 返回 503
+    pub health_max_slot_lag: u64,
+    /// 扫描落后少于该 slot 数时视为已追上链头
+    pub catch_up_threshold_slots: u64,
+    /// 只记录这些程序的指令，None 或空表示不限制
+    pub program_allowlist: Option<Vec<String>>,
+    /// 始终忽略这些程序的指令
+    pub program_denylist: Option<Vec<String>>,
+    /// 是否记录执行失败的交易，关闭后只保存成功的交易
+    pub store_failed_transactions: bool,
+    /// 低于该 lamports 的 SOL 转账不记录，0 表示不过滤
+    pub min_lamports: u64,
+    /// 低于该数量（原始单位）的代币转账不记录，0 表示不过滤
+    pub min_token_amount: u64,
+    /// RPC 写操作接口的 API Key，支持多个；未设置时不鉴权
+    pub rpc_api_key: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanMode {
+    /// 定时轮询 get_slot
+    Poll,
+    /// 通过 PubSub WebSocket 订阅新 slot
+    Stream,
+}
+
+impl std::str::FromStr for ScanMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "poll" => Ok(ScanMode::Poll),
+            "stream" => Ok(ScanMode::Stream),
+            other => Err(format!("unknown scan mode: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// 便于人工阅读的文本格式
+    Text,
+    /// 每行一个 JSON 对象，包含当前 span 及其字段，供 ELK/Loki 采集
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn to_commitment_config(self) -> CommitmentConfig {
+        match self {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+impl std::str::FromStr for Commitment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "processed" => Ok(Commitment::Processed),
+            "confirmed" => Ok(Commitment::Confirmed),
+            "finalized" => Ok(Commitment::Finalized),
+            other => Err(format!("unknown commitment level: {}", other)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct KafkaConfig {
+    /// 关闭后不创建 Kafka producer，交易只通过 WebSocket 推送
+    pub enabled: bool,
     pub brokers: String,
     pub transaction_topic: String,
+    /// 按交易类型单独投递的 topic，未配置时使用 transaction_topic
+    pub native_topic: Option<String>,
+    pub token_topic: Option<String>,
+    pub nft_topic: Option<String>,
     pub client_id: String,
+    pub event_topic: String,
+    /// 关注地址增删事件的 topic
+    pub address_events_topic: String,
+    pub key_strategy: KafkaKeyStrategy,
+    /// 单条消息的投递超时
+    pub send_timeout_ms: u64,
+    /// 连续失败多少次后熔断
+    pub circuit_failure_threshold: u32,
+    /// 熔断后暂停发送的时长，期间消息直接写入死信集合
+    pub circuit_cooldown_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KafkaKeyStrategy {
+    /// 以交易签名为 key
+    Signature,
+    /// 以命中的关注地址为 key，同一钱包的消息（无论收款还是付款）落在同一分区并保持有序
+    Address,
+}
+
+impl std::str::FromStr for KafkaKeyStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "signature" => Ok(KafkaKeyStrategy::Signature),
+            "address" => Ok(KafkaKeyStrategy::Address),
+            other => Err(format!("unknown kafka key strategy: {}", other)),
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            solana_rpc_urls: vec!["https://api.mainnet-beta.solana.com".to_string()],
+            // 为空时由主 RPC 地址推导
+            solana_ws_url: String::new(),
+            scan_mode: ScanMode::Poll,
+            log_format: LogFormat::Text,
+            commitment: Commitment::Confirmed,
+            mongodb_uri: "mongodb://localhost:27017".to_string(),
+            mongo_max_pool_size: None,
+            mongo_min_pool_size: None,
+            mongo_connect_timeout_ms: 10_000,
+            mongo_connect_retries: 5,
+            transaction_ttl_days: None,
+            dry_run: false,
+            kafka_config: KafkaConfig::default(),
+            bind_host: "0.0.0.0".to_string(),
+            rpc_port: 8080,
+            websocket_port: 8081,
+            single_port: false,
+            ws_auth_token: None,
+            tls_cert_path: None,
+            tls_key_path: None,
+            ws_max_connections: 1000,
+            ws_channel_capacity: 256,
+            webhook_url: None,
+            webhook_secret: None,
+            webhook_timeout_ms: 5000,
+            webhook_max_retries: 3,
+            scan_interval_secs: 5,
+            scan_interval_ms: None,
+            max_addresses: 100000,
+            max_concurrent_requests: 16,
+            max_slots_per_tick: 1000,
+            reorg_depth: 32,
+            initial_lookback_slots: 300,
+            start_slot: None,
+            shard_index: 0,
+            shard_count: 1,
+            leader_election: false,
+            leader_lease_secs: 30,
+            instance_id: None,
+            block_cache_size: 1024,
+            max_retries: 5,
+            retry_base_delay_ms: 500,
+            rpc_timeout_secs: 30,
+            rpc_requests_per_second: 0,
+            missed_slot_retry_secs: 30,
+            token_metadata_ttl_secs: 3600,
+            address_reload_secs: 60,
+            health_max_slot_lag: 150,
+            catch_up_threshold_slots: 32,
+            program_allowlist: None,
+            program_denylist: None,
+            store_failed_transactions: true,
+            min_lamports: 0,
+            min_token_amount: 0,
+            rpc_api_key: None,
+        }
+    }
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            brokers: "localhost:9092".to_string(),
+            transaction_topic: "solana_transactions".to_string(),
+            native_topic: None,
+            token_topic: None,
+            nft_topic: None,
+            client_id: "solana_scanner".to_string(),
+            event_topic: "solana_scanner_events".to_string(),
+            address_events_topic: "solana_scanner_address_events".to_string(),
+            key_strategy: KafkaKeyStrategy::Signature,
+            send_timeout_ms: 5000,
+            circuit_failure_threshold: 5,
+            circuit_cooldown_secs: 30,
+        }
+    }
 }
 
 impl AppConfig {
+    /// 设置了 CONFIG_FILE 时先读取配置文件，再用环境变量覆盖
     pub fn load() -> Result<Self> {
         dotenv::dotenv().ok();
 
-        let config = AppConfig {
-            solana_rpc_url: env::var("SOLANA_RPC_URL")
-                .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
-            mongodb_uri: env::var("MONGODB_URI")
-                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
-            kafka_config: KafkaConfig {
-                brokers: env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string()),
-                transaction_topic: env::var("KAFKA_TRANSACTION_TOPIC")
-                    .unwrap_or_else(|_| "solana_transactions".to_string()),
-                client_id: env::var("KAFKA_CLIENT_ID")
-                    .unwrap_or_else(|_| "solana_scanner".to_string()),
-            },
-            rpc_port: env::var("RPC_PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse()
-                .unwrap_or(8080),
-            websocket_port: env::var("WEBSOCKET_PORT")
-                .unwrap_or_else(|_| "8081".to_string())
-                .parse()
-                .unwrap_or(8081),
-            scan_interval_secs: env::var("SCAN_INTERVAL_SECS")
-                .unwrap_or_else(|_| "5".to_string())
-                .parse()
-                .unwrap_or(5),
-            max_addresses: env::var("MAX_ADDRESSES")
-                .unwrap_or_else(|_| "100000".to_string())
-                .parse()
-                .unwrap_or(100000),
-            max_concurrent_requests: env::var("MAX_CONCURRENT_REQUESTS")
-                .unwrap_or_else(|_| "16".to_string())
-                .parse()
-                .unwrap_or(16),
+        let mut config = match env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) => {
+                let mut config = Self::default();
+                config.apply_env_overrides()?;
+                config
+            }
         };
+        if env::args().any(|arg| arg == "--dry-run") {
+            config.dry_run = true;
+        }
+        Ok(config)
+    }
 
+    /// 解析 TOML/YAML 配置文件（按扩展名识别），未填写的字段取默认值，环境变量优先
+    pub fn from_file(path: &str) -> Result<Self> {
+        let mut config: AppConfig = ::config::Config::builder()
+            .add_source(::config::File::with_name(path))
+            .build()?
+            .try_deserialize()?;
+        config.apply_env_overrides()?;
         Ok(config)
     }
+
+    /// 实际使用的扫描间隔，scan_interval_ms 优先
+    pub fn scan_interval(&self) -> std::time::Duration {
+        match self.scan_interval_ms {
+            Some(ms) => std::time::Duration::from_millis(ms),
+            None => std::time::Duration::from_secs(self.scan_interval_secs),
+        }
+    }
+
+    /// RPC 服务监听地址，bind_host 已在 validate 中校验
+    pub fn rpc_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_ip(), self.rpc_port)
+    }
+
+    /// WebSocket 服务监听地址
+    pub fn websocket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_ip(), self.websocket_port)
+    }
+
+    fn bind_ip(&self) -> IpAddr {
+        self.bind_host.parse().unwrap_or(IpAddr::from([0, 0, 0, 0]))
+    }
+
+    /// 启动前校验配置，任何不合法的值都直接报错退出
+    pub fn validate(&self) -> ScannerResult<()> {
+        if self.solana_rpc_urls.is_empty() {
+            return Err(config_error(
+                "at least one Solana RPC URL must be configured",
+            ));
+        }
+        for rpc_url in &self.solana_rpc_urls {
+            check_url(rpc_url, &["http", "https"])?;
+        }
+        check_url(&self.solana_ws_url, &["ws", "wss"])?;
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err(config_error(
+                "TLS_CERT_PATH and TLS_KEY_PATH must be set together",
+            ));
+        }
+        if let Some(webhook_url) = &self.webhook_url {
+            check_url(webhook_url, &["http", "https"])?;
+        }
+
+        if self.bind_host.parse::<IpAddr>().is_err() {
+            return Err(config_error(format!(
+                "BIND_HOST must be an IP address, got {}",
+                self.bind_host
+            )));
+        }
+        if self.rpc_port == 0 || self.websocket_port == 0 {
+            return Err(config_error("RPC_PORT and WEBSOCKET_PORT must be nonzero"));
+        }
+        if !self.single_port && self.rpc_port == self.websocket_port {
+            return Err(config_error(format!(
+                "RPC_PORT and WEBSOCKET_PORT must differ (both are {})",
+                self.rpc_port
+            )));
+        }
+        if self.scan_interval().is_zero() {
+            return Err(config_error(
+                "SCAN_INTERVAL_SECS / SCAN_INTERVAL_MS must be greater than 0",
+            ));
+        }
+        if self.rpc_timeout_secs == 0 {
+            return Err(config_error("RPC_TIMEOUT_SECS must be greater than 0"));
+        }
+        if self.max_slots_per_tick == 0 {
+            return Err(config_error("MAX_SLOTS_PER_TICK must be greater than 0"));
+        }
+        if self.max_addresses == 0 {
+            return Err(config_error("MAX_ADDRESSES must be greater than 0"));
+        }
+        if self.shard_count == 0 {
+            return Err(config_error("SHARD_COUNT must be greater than 0"));
+        }
+        if self.shard_index >= self.shard_count {
+            return Err(config_error("SHARD_INDEX must be less than SHARD_COUNT"));
+        }
+        if self.leader_election && self.leader_lease_secs < 3 {
+            return Err(config_error("LEADER_LEASE_SECS must be at least 3"));
+        }
+        if let (Some(min), Some(max)) = (self.mongo_min_pool_size, self.mongo_max_pool_size) {
+            if min > max {
+                return Err(config_error(
+                    "MONGO_MIN_POOL_SIZE must not exceed MONGO_MAX_POOL_SIZE",
+                ));
+            }
+        }
+        if self.transaction_ttl_days == Some(0) {
+            return Err(config_error("TRANSACTION_TTL_DAYS must be greater than 0"));
+        }
+        if self.block_cache_size == 0 {
+            return Err(config_error("BLOCK_CACHE_SIZE must be greater than 0"));
+        }
+        if self.ws_max_connections == 0 {
+            return Err(config_error("WS_MAX_CONNECTIONS must be greater than 0"));
+        }
+        // mpsc::channel 的容量不能为 0
+        if self.ws_channel_capacity == 0 {
+            return Err(config_error("WS_CHANNEL_CAPACITY must be greater than 0"));
+        }
+        if self.kafka_config.enabled && self.kafka_config.brokers.trim().is_empty() {
+            return Err(config_error(
+                "KAFKA_BROKERS must not be empty when KAFKA_ENABLED is true",
+            ));
+        }
+        if self.kafka_config.send_timeout_ms == 0 {
+            return Err(config_error("KAFKA_SEND_TIMEOUT_MS must be greater than 0"));
+        }
+        if self.kafka_config.circuit_failure_threshold == 0 {
+            return Err(config_error(
+                "KAFKA_CIRCUIT_FAILURE_THRESHOLD must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn apply_env_overrides(&mut self) -> ScannerResult<()> {
+        // SOLANA_RPC_URLS 为逗号分隔的多个端点，第一个为主端点；未设置时使用 SOLANA_RPC_URL
+        if let Ok(urls) = env::var("SOLANA_RPC_URLS").or_else(|_| env::var("SOLANA_RPC_URL")) {
+            self.solana_rpc_urls = urls
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect();
+        }
+        env_override("SOLANA_WS_URL", &mut self.solana_ws_url)?;
+        // 未配置 PubSub 地址时由主 RPC 地址推导（http -> ws, https -> wss）
+        if self.solana_ws_url.is_empty() {
+            self.solana_ws_url = self
+                .solana_rpc_urls
+                .first()
+                .map(|url| url.replacen("http", "ws", 1))
+                .unwrap_or_else(|| "wss://api.mainnet-beta.solana.com".to_string());
+        }
+        env_override("SCAN_MODE", &mut self.scan_mode)?;
+        env_override("LOG_FORMAT", &mut self.log_format)?;
+        env_override("COMMITMENT", &mut self.commitment)?;
+        env_override("MONGODB_URI", &mut self.mongodb_uri)?;
+        env_override_opt("MONGO_MAX_POOL_SIZE", &mut self.mongo_max_pool_size)?;
+        env_override_opt("MONGO_MIN_POOL_SIZE", &mut self.mongo_min_pool_size)?;
+        env_override(
+            "MONGO_CONNECT_TIMEOUT_MS",
+            &mut self.mongo_connect_timeout_ms,
+        )?;
+        env_override("MONGO_CONNECT_RETRIES", &mut self.mongo_connect_retries)?;
+        env_override_opt("TRANSACTION_TTL_DAYS", &mut self.transaction_ttl_days)?;
+        env_override("DRY_RUN", &mut self.dry_run)?;
+
+        let kafka = &mut self.kafka_config;
+        env_override("KAFKA_ENABLED", &mut kafka.enabled)?;
+        env_override("KAFKA_BROKERS", &mut kafka.brokers)?;
+        env_override("KAFKA_TRANSACTION_TOPIC", &mut kafka.transaction_topic)?;
+        env_override_opt("KAFKA_NATIVE_TOPIC", &mut kafka.native_topic)?;
+        env_override_opt("KAFKA_TOKEN_TOPIC", &mut kafka.token_topic)?;
+        env_override_opt("KAFKA_NFT_TOPIC", &mut kafka.nft_topic)?;
+        env_override("KAFKA_CLIENT_ID", &mut kafka.client_id)?;
+        env_override("KAFKA_EVENT_TOPIC", &mut kafka.event_topic)?;
+        env_override(
+            "KAFKA_ADDRESS_EVENTS_TOPIC",
+            &mut kafka.address_events_topic,
+        )?;
+        env_override("KAFKA_KEY_STRATEGY", &mut kafka.key_strategy)?;
+        env_override("KAFKA_SEND_TIMEOUT_MS", &mut kafka.send_timeout_ms)?;
+        env_override(
+            "KAFKA_CIRCUIT_FAILURE_THRESHOLD",
+            &mut kafka.circuit_failure_threshold,
+        )?;
+        env_override(
+            "KAFKA_CIRCUIT_COOLDOWN_SECS",
+            &mut kafka.circuit_cooldown_secs,
+        )?;
+
+        env_override("BIND_HOST", &mut self.bind_host)?;
+        env_override("RPC_PORT", &mut self.rpc_port)?;
+        env_override("WEBSOCKET_PORT", &mut self.websocket_port)?;
+        env_override("SINGLE_PORT", &mut self.single_port)?;
+        env_override_opt("WS_AUTH_TOKEN", &mut self.ws_auth_token)?;
+        self.ws_auth_token = self.ws_auth_token.take().filter(|token| !token.is_empty());
+        env_override_opt("TLS_CERT_PATH", &mut self.tls_cert_path)?;
+        self.tls_cert_path = self.tls_cert_path.take().filter(|path| !path.is_empty());
+        env_override_opt("TLS_KEY_PATH", &mut self.tls_key_path)?;
+        self.tls_key_path = self.tls_key_path.take().filter(|path| !path.is_empty());
+        env_override("WS_MAX_CONNECTIONS", &mut self.ws_max_connections)?;
+        env_override("WS_CHANNEL_CAPACITY", &mut self.ws_channel_capacity)?;
+        env_override_opt("WEBHOOK_URL", &mut self.webhook_url)?;
+        self.webhook_url = self.webhook_url.take().filter(|url| !url.is_empty());
+        env_override_opt("WEBHOOK_SECRET", &mut self.webhook_secret)?;
+        self.webhook_secret = self
+            .webhook_secret
+            .take()
+            .filter(|secret| !secret.is_empty());
+        env_override("WEBHOOK_TIMEOUT_MS", &mut self.webhook_timeout_ms)?;
+        env_override("WEBHOOK_MAX_RETRIES", &mut self.webhook_max_retries)?;
+        env_override("SCAN_INTERVAL_SECS", &mut self.scan_interval_secs)?;
+        env_override_opt("SCAN_INTERVAL_MS", &mut self.scan_interval_ms)?;
+        env_override("MAX_ADDRESSES", &mut self.max_addresses)?;
+        env_override("MAX_CONCURRENT_REQUESTS", &mut self.max_concurrent_requests)?;
+        env_override("MAX_SLOTS_PER_TICK", &mut self.max_slots_per_tick)?;
+        env_override("REORG_DEPTH", &mut self.reorg_depth)?;
+        env_override("INITIAL_LOOKBACK_SLOTS", &mut self.initial_lookback_slots)?;
+        env_override_opt("START_SLOT", &mut self.start_slot)?;
+        env_override("SHARD_INDEX", &mut self.shard_index)?;
+        env_override("SHARD_COUNT", &mut self.shard_count)?;
+        env_override("LEADER_ELECTION", &mut self.leader_election)?;
+        env_override("LEADER_LEASE_SECS", &mut self.leader_lease_secs)?;
+        env_override_opt("INSTANCE_ID", &mut self.instance_id)?;
+        self.instance_id = self.instance_id.take().filter(|id| !id.is_empty());
+        env_override("BLOCK_CACHE_SIZE", &mut self.block_cache_size)?;
+        env_override("RPC_MAX_RETRIES", &mut self.max_retries)?;
+        env_override("RPC_RETRY_BASE_DELAY_MS", &mut self.retry_base_delay_ms)?;
+        env_override("RPC_TIMEOUT_SECS", &mut self.rpc_timeout_secs)?;
+        env_override("RPC_REQUESTS_PER_SECOND", &mut self.rpc_requests_per_second)?;
+        env_override("MISSED_SLOT_RETRY_SECS", &mut self.missed_slot_retry_secs)?;
+        env_override("TOKEN_METADATA_TTL_SECS", &mut self.token_metadata_ttl_secs)?;
+        env_override("ADDRESS_RELOAD_SECS", &mut self.address_reload_secs)?;
+        env_override("HEALTH_MAX_SLOT_LAG", &mut self.health_max_slot_lag)?;
+        env_override(
+            "CATCH_UP_THRESHOLD_SLOTS",
+            &mut self.catch_up_threshold_slots,
+        )?;
+        env_override_list("PROGRAM_ALLOWLIST", &mut self.program_allowlist);
+        env_override_list("PROGRAM_DENYLIST", &mut self.program_denylist);
+        env_override(
+            "STORE_FAILED_TRANSACTIONS",
+            &mut self.store_failed_transactions,
+        )?;
+        env_override("MIN_LAMPORTS", &mut self.min_lamports)?;
+        env_override("MIN_TOKEN_AMOUNT", &mut self.min_token_amount)?;
+        env_override_list("RPC_API_KEY", &mut self.rpc_api_key);
+        Ok(())
+    }
 }
+
+/// 环境变量存在时覆盖目标值，无法解析则报错而不是回退默认值
+fn env_override<T>(key: &str, target: &mut T) -> ScannerResult<()>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(key) {
+        *target = parse_env(key, &value)?;
+    }
+    Ok(())
+}
+
+fn env_override_opt<T>(key: &str, target: &mut Option<T>) -> ScannerResult<()>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(key) {
+        *target = Some(parse_env(key, &value)?);
+    }
+    Ok(())
+}
+
+/// 逗号分隔的列表
+fn env_override_list(key: &str, target: &mut Option<Vec<String>>) {
+    if let Ok(value) = env::var(key) {
+        *target = Some(
+            value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+        );
+    }
+}
+
+fn parse_env<T>(key: &str, value: &str) -> ScannerResult<T>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| config_error(format!("invalid {}={:?}: {}", key, value, e)))
+}
+
+fn check_url(value: &str, schemes: &[&str]) -> ScannerResult<()> {
+    let parsed = url::Url::parse(value)
+        .map_err(|e| config_error(format!("invalid URL {:?}: {}", value, e)))?;
+    if !schemes.contains(&parsed.scheme()) {
+        return Err(config_error(format!(
+            "URL {:?} must use one of the schemes {:?}",
+            value, schemes
+        )));
+    }
+    Ok(())
+}
+
+fn config_error(message: impl Into<String>) -> ScannerError {
+    ScannerError::ConfigError(message.into())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,132 @@
+use super::*;
+
+fn write_config(name: &str, content: &str) -> String {
+    let path = env::temp_dir().join(format!("solana-scan-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_from_file_toml_with_defaults() {
+    let path = write_config(
+        "defaults.toml",
+        r#"
+scan_mode = "stream"
+solana_rpc_urls = ["https://rpc.example.com"]
+
+[kafka_config]
+brokers = "kafka-1:9092,kafka-2:9092"
+nft_topic = "solana_nft"
+"#,
+    );
+
+    let config = AppConfig::from_file(&path).unwrap();
+    assert_eq!(config.scan_mode, ScanMode::Stream);
+    assert_eq!(config.solana_ws_url, "wss://rpc.example.com");
+    assert_eq!(config.kafka_config.brokers, "kafka-1:9092,kafka-2:9092");
+    assert_eq!(config.kafka_config.nft_topic.as_deref(), Some("solana_nft"));
+    // 文件中未填写的字段取默认值
+    assert_eq!(config.kafka_config.transaction_topic, "solana_transactions");
+    assert_eq!(config.reorg_depth, 32);
+}
+
+#[test]
+fn test_from_file_env_overrides_file() {
+    let path = write_config(
+        "override.yaml",
+        "token_metadata_ttl_secs: 120\nmissed_slot_retry_secs: 10\n",
+    );
+
+    env::set_var("TOKEN_METADATA_TTL_SECS", "900");
+    let config = AppConfig::from_file(&path).unwrap();
+    env::remove_var("TOKEN_METADATA_TTL_SECS");
+
+    assert_eq!(config.token_metadata_ttl_secs, 900);
+    assert_eq!(config.missed_slot_retry_secs, 10);
+}
+
+#[test]
+fn test_validate_default_config() {
+    let config = AppConfig {
+        solana_ws_url: "wss://api.mainnet-beta.solana.com".to_string(),
+        ..Default::default()
+    };
+    assert!(config.validate().is_ok());
+    assert_eq!(config.rpc_addr().to_string(), "0.0.0.0:8080");
+    assert_eq!(config.websocket_addr().to_string(), "0.0.0.0:8081");
+}
+
+#[test]
+fn test_validate_rejects_invalid_values() {
+    let config = AppConfig {
+        solana_ws_url: "wss://api.mainnet-beta.solana.com".to_string(),
+        ..Default::default()
+    };
+
+    let mut same_ports = config.clone();
+    same_ports.websocket_port = same_ports.rpc_port;
+    assert!(matches!(
+        same_ports.validate(),
+        Err(ScannerError::ConfigError(_))
+    ));
+    // 单端口模式不使用 websocket_port
+    same_ports.single_port = true;
+    assert!(same_ports.validate().is_ok());
+
+    let mut bad_url = config.clone();
+    bad_url.solana_rpc_urls = vec!["not a url".to_string()];
+    assert!(bad_url.validate().is_err());
+
+    let mut zero_interval = config.clone();
+    zero_interval.scan_interval_secs = 0;
+    assert!(zero_interval.validate().is_err());
+
+    let mut bad_pool = config.clone();
+    bad_pool.mongo_min_pool_size = Some(10);
+    bad_pool.mongo_max_pool_size = Some(5);
+    assert!(bad_pool.validate().is_err());
+
+    let mut bad_shard = config.clone();
+    bad_shard.shard_count = 2;
+    bad_shard.shard_index = 2;
+    assert!(bad_shard.validate().is_err());
+
+    let mut bad_host = config.clone();
+    bad_host.bind_host = "localhost".to_string();
+    assert!(bad_host.validate().is_err());
+
+    let mut cert_only = config.clone();
+    cert_only.tls_cert_path = Some("cert.pem".to_string());
+    assert!(cert_only.validate().is_err());
+
+    let mut no_brokers = config;
+    no_brokers.kafka_config.brokers = " ".to_string();
+    assert!(no_brokers.validate().is_err());
+    // 关闭 Kafka 时不要求配置 broker
+    no_brokers.kafka_config.enabled = false;
+    assert!(no_brokers.validate().is_ok());
+}
+
+#[test]
+fn test_env_override_rejects_unparseable_value() {
+    let key = "SOLANA_SCAN_TEST_INVALID_PORT";
+    env::set_var(key, "80a80");
+    let mut port: u16 = 8080;
+    let result = env_override(key, &mut port);
+    env::remove_var(key);
+
+    assert!(matches!(result, Err(ScannerError::ConfigError(_))));
+    assert_eq!(port, 8080);
+}
+
+#[test]
+fn test_scan_interval_prefers_millis() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.scan_interval(), std::time::Duration::from_secs(5));
+
+    config.scan_interval_ms = Some(400);
+    assert_eq!(
+        config.scan_interval(),
+        std::time::Duration::from_millis(400)
+    );
+}
@@ -1,26 +1,127 @@
 use anyhow::Result;
-use mongodb::{Client, Database};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::error::ErrorKind;
+use mongodb::options::ClientOptions;
+use mongodb::{Client, Collection, Database, IndexModel};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::utils::retry::backoff_delay;
 
 pub mod repos;
 
 pub use repos::*;
 
-pub async fn init_mongodb(uri: &str) -> Result<Database> {
-    let client = Client::with_uri_str(uri).await?;
+/// 启动时连接 MongoDB 的重试基础间隔
+const CONNECT_RETRY_BASE_DELAY_MS: u64 = 1000;
+
+// 索引已存在或定义冲突的错误码，重启时忽略
+const INDEX_ALREADY_EXISTS: i32 = 68;
+const INDEX_OPTIONS_CONFLICT: i32 = 85;
+const INDEX_KEY_SPECS_CONFLICT: i32 = 86;
+
+pub async fn init_mongodb(config: &AppConfig) -> Result<Database> {
+    let mut attempt = 0;
+    let client = loop {
+        match connect(config).await {
+            Ok(client) => break client,
+            Err(e) if attempt < config.mongo_connect_retries => {
+                let delay = backoff_delay(CONNECT_RETRY_BASE_DELAY_MS, attempt);
+                warn!(
+                    "Failed to connect to MongoDB (attempt {}/{}): {}, retrying in {:?}",
+                    attempt + 1,
+                    config.mongo_connect_retries + 1,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    info!("Connected to MongoDB");
     let database = client.database("solana_scanner");
 
     // 创建索引
-    create_indexes(&database).await?;
+    create_indexes(&database, config.transaction_ttl_days).await?;
 
     Ok(database)
 }
 
-async fn create_indexes(database: &Database) -> Result<()> {
-    use mongodb::bson::doc;
-    use mongodb::IndexModel;
+/// 建立连接并 ping 一次，Client 本身是惰性连接的，不 ping 无法发现 MongoDB 不可用
+async fn connect(config: &AppConfig) -> Result<Client> {
+    let mut options = ClientOptions::parse(&config.mongodb_uri).await?;
+    options.connect_timeout = Some(Duration::from_millis(config.mongo_connect_timeout_ms));
+    options.server_selection_timeout = Some(Duration::from_millis(config.mongo_connect_timeout_ms));
+    if config.mongo_max_pool_size.is_some() {
+        options.max_pool_size = config.mongo_max_pool_size;
+    }
+    if config.mongo_min_pool_size.is_some() {
+        options.min_pool_size = config.mongo_min_pool_size;
+    }
+
+    let client = Client::with_options(options)?;
+    client
+        .database("admin")
+        .run_command(doc! { "ping": 1 }, None)
+        .await?;
+    Ok(client)
+}
+
+/// 创建索引，索引已存在（包括定义不一致）时只记录警告，保证重启不会失败
+async fn ensure_index(collection: &Collection<Document>, index: IndexModel) -> Result<()> {
+    match collection.create_index(index, None).await {
+        Ok(_) => Ok(()),
+        Err(e) => match *e.kind {
+            ErrorKind::Command(ref command_error)
+                if matches!(
+                    command_error.code,
+                    INDEX_ALREADY_EXISTS | INDEX_OPTIONS_CONFLICT | INDEX_KEY_SPECS_CONFLICT
+                ) =>
+            {
+                warn!(
+                    "Index on {} already exists, skipping: {}",
+                    collection.name(),
+                    command_error.message
+                );
+                Ok(())
+            }
+            _ => Err(e.into()),
+        },
+    }
+}
+
+/// 删除旧版本在 transactions 上创建、已被记录键取代的索引：
+/// signature 唯一索引，以及以 signature 作为次级排序的分页索引
+async fn drop_legacy_transaction_indexes(collection: &Collection<Document>) -> Result<()> {
+    let legacy_sort_keys = [
+        doc! { "timestamp": -1, "signature": -1 },
+        doc! { "amount": -1, "signature": -1 },
+    ];
+    let mut legacy = Vec::new();
+    let mut indexes = collection.list_indexes(None).await?;
+    while let Some(index) = indexes.try_next().await? {
+        let unique = index.options.as_ref().and_then(|o| o.unique) == Some(true);
+        let unique_signature = unique && index.keys == doc! { "signature": 1 };
+        if unique_signature || legacy_sort_keys.contains(&index.keys) {
+            if let Some(name) = index.options.and_then(|o| o.name) {
+                legacy.push(name);
+            }
+        }
+    }
+    for name in legacy {
+        warn!("Dropping legacy index {} on transactions", name);
+        collection.drop_index(name, None).await?;
+    }
+    Ok(())
+}
 
+async fn create_indexes(database: &Database, transaction_ttl_days: Option<u64>) -> Result<()> {
     // 钱包地址索引
-    let wallet_collection = database.collection::<mongodb::bson::Document>("wallet_addresses");
+    let wallet_collection = database.collection::<Document>("wallet_addresses");
     let wallet_index = IndexModel::builder()
         .keys(doc! { "address": 1 })
         .options(
@@ -29,23 +130,34 @@ async fn create_indexes(database: &Database) -> Result<()> {
                 .build(),
         )
         .build();
-    wallet_collection.create_index(wallet_index, None).await?;
+    ensure_index(&wallet_collection, wallet_index).await?;
 
     // 交易索引
-    let transaction_collection = database.collection::<mongodb::bson::Document>("transactions");
+    let transaction_collection = database.collection::<Document>("transactions");
 
-    // 签名索引
-    let signature_index = IndexModel::builder()
-        .keys(doc! { "signature": 1 })
+    // 同一签名可以有多条记录（多笔转账、CPI、账户事件），唯一性由记录键保证。
+    // 旧版本在 signature 上建了唯一索引和分页索引，先删除；旧记录没有记录键，以签名补齐
+    drop_legacy_transaction_indexes(&transaction_collection).await?;
+    transaction_collection
+        .update_many(
+            doc! { "record_key": { "$exists": false } },
+            vec![doc! { "$set": { "record_key": "$signature" } }],
+            None,
+        )
+        .await?;
+    let record_key_index = IndexModel::builder()
+        .keys(doc! { "record_key": 1 })
         .options(
             mongodb::options::IndexOptions::builder()
                 .unique(true)
                 .build(),
         )
         .build();
-    transaction_collection
-        .create_index(signature_index, None)
-        .await?;
+    ensure_index(&transaction_collection, record_key_index).await?;
+
+    // 签名索引
+    let signature_index = IndexModel::builder().keys(doc! { "signature": 1 }).build();
+    ensure_index(&transaction_collection, signature_index).await?;
 
     // 地址和时间索引
     let address_time_index = IndexModel::builder()
@@ -54,9 +166,7 @@ async fn create_indexes(database: &Database) -> Result<()> {
             "timestamp": -1
         })
         .build();
-    transaction_collection
-        .create_index(address_time_index, None)
-        .await?;
+    ensure_index(&transaction_collection, address_time_index).await?;
 
     let to_address_time_index = IndexModel::builder()
         .keys(doc! {
@@ -64,9 +174,78 @@ async fn create_indexes(database: &Database) -> Result<()> {
             "timestamp": -1
         })
         .build();
-    transaction_collection
-        .create_index(to_address_time_index, None)
-        .await?;
+    ensure_index(&transaction_collection, to_address_time_index).await?;
+
+    // 游标分页排序索引，记录键作为次级排序保证同一签名的多条记录也有确定顺序
+    let timestamp_record_key_index = IndexModel::builder()
+        .keys(doc! {
+            "timestamp": -1,
+            "record_key": -1
+        })
+        .build();
+    ensure_index(&transaction_collection, timestamp_record_key_index).await?;
+
+    // 按金额排序索引
+    let amount_record_key_index = IndexModel::builder()
+        .keys(doc! {
+            "amount": -1,
+            "record_key": -1
+        })
+        .build();
+    ensure_index(&transaction_collection, amount_record_key_index).await?;
+
+    // 交易过期 TTL 索引，timestamp 必须是 BSON Date 才会生效；
+    // 修改保留天数后已有索引不会自动更新，需要手动 collMod 或删除重建
+    if let Some(days) = transaction_ttl_days {
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "timestamp": 1 })
+            .options(
+                mongodb::options::IndexOptions::builder()
+                    .expire_after(Duration::from_secs(days * 24 * 60 * 60))
+                    .build(),
+            )
+            .build();
+        ensure_index(&transaction_collection, ttl_index).await?;
+    }
+
+    // 区块哈希索引，用于分叉检测
+    let block_hash_collection = database.collection::<Document>("block_hashes");
+    let slot_index = IndexModel::builder()
+        .keys(doc! { "slot": 1 })
+        .options(
+            mongodb::options::IndexOptions::builder()
+                .unique(true)
+                .build(),
+        )
+        .build();
+    ensure_index(&block_hash_collection, slot_index).await?;
+
+    // 地址统计索引
+    let address_stats_collection = database.collection::<Document>("address_stats");
+    let address_stats_index = IndexModel::builder()
+        .keys(doc! { "address": 1 })
+        .options(
+            mongodb::options::IndexOptions::builder()
+                .unique(true)
+                .build(),
+        )
+        .build();
+    ensure_index(&address_stats_collection, address_stats_index).await?;
+
+    // 扫描失败的 slot 索引
+    let missed_slot_collection = database.collection::<Document>("missed_slots");
+    let missed_slot_index = IndexModel::builder()
+        .keys(doc! { "slot": 1 })
+        .options(
+            mongodb::options::IndexOptions::builder()
+                .unique(true)
+                .build(),
+        )
+        .build();
+    ensure_index(&missed_slot_collection, missed_slot_index).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests;
@@ -1,9 +1,25 @@
 use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::error::ErrorKind;
 use mongodb::{Collection, Database};
 
-use crate::models::{ScanStatus, Transaction, WalletAddress};
+use crate::models::{
+    AddressQuery, AddressStats, BlockHash, KafkaDeadLetter, MissedSlot, ScanStatus, ScannerLock,
+    StatsSummary, TimeBucket, TimeInterval, Transaction, TransactionPage, TransactionQuery,
+    TransactionSort, WalletAddress,
+};
+use crate::utils::error::ScannerError;
+use crate::utils::validation::validate_address;
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+/// 单次查询最多支持的地址数
+pub const MAX_QUERY_ADDRESSES: usize = 100;
+const DUPLICATE_KEY_ERROR: i32 = 11000;
+/// 单条 update 命令最多携带的语句数，避免超过 16MB 的命令大小限制
+const BULK_WRITE_BATCH_SIZE: usize = 1000;
 
 pub struct WalletAddressRepo {
     collection: Collection<WalletAddress>,
@@ -15,12 +31,35 @@ impl WalletAddressRepo {
         Self { collection }
     }
 
+    /// 地址已存在（包括已停用的）时重新启用并更新标签，否则插入新记录
     pub async fn insert_address(&self, address: &str, label: Option<&str>) -> Result<()> {
+        validate_address(address)?;
+        if self.reactivate_address(address, label).await? {
+            return Ok(());
+        }
+
         let wallet_address = WalletAddress::new(address.to_string(), label.map(|s| s.to_string()));
         self.collection.insert_one(&wallet_address, None).await?;
         Ok(())
     }
 
+    /// 重新启用已有地址，label 为 None 时保留原标签；返回 false 表示地址不存在
+    pub async fn reactivate_address(&self, address: &str, label: Option<&str>) -> Result<bool> {
+        let mut update = doc! {
+            "is_active": true,
+            "updated_at": mongodb::bson::DateTime::now(),
+        };
+        if let Some(label) = label {
+            update.insert("label", label);
+        }
+
+        let result = self
+            .collection
+            .update_one(doc! { "address": address }, doc! { "$set": update }, None)
+            .await?;
+        Ok(result.matched_count > 0)
+    }
+
     pub async fn get_all_active_addresses(&self) -> Result<Vec<WalletAddress>> {
         let cursor = self
             .collection
@@ -31,10 +70,45 @@ impl WalletAddressRepo {
         Ok(addresses)
     }
 
-    pub async fn deactivate_address(&self, address: &str) -> Result<()> {
-        self.collection
+    /// 按 created_at 升序分页，保证翻页时顺序稳定
+    pub async fn get_active_addresses_paged(
+        &self,
+        query: &AddressQuery,
+    ) -> Result<Vec<WalletAddress>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "created_at": 1, "_id": 1 })
+            .limit(query.effective_limit() as i64)
+            .skip(query.offset.unwrap_or(0) as u64)
+            .build();
+        let cursor = self.collection.find(address_filter(query), options).await?;
+
+        let addresses: Vec<WalletAddress> = cursor.try_collect().await?;
+        Ok(addresses)
+    }
+
+    /// 满足搜索条件的有效地址数量，忽略 limit/offset
+    pub async fn count_addresses(&self, query: &AddressQuery) -> Result<u64> {
+        let count = self
+            .collection
+            .count_documents(address_filter(query), None)
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn count_active_addresses(&self) -> Result<u64> {
+        let count = self
+            .collection
+            .count_documents(doc! { "is_active": true }, None)
+            .await?;
+        Ok(count)
+    }
+
+    /// 停用地址，返回 false 表示地址不存在或已停用
+    pub async fn deactivate_address(&self, address: &str) -> Result<bool> {
+        let result = self
+            .collection
             .update_one(
-                doc! { "address": address },
+                doc! { "address": address, "is_active": true },
                 doc! {
                     "$set": {
                         "is_active": false,
@@ -44,49 +118,166 @@ impl WalletAddressRepo {
                 None,
             )
             .await?;
-        Ok(())
+        Ok(result.modified_count > 0)
     }
 }
 
+/// 有效地址的搜索条件：search 匹配地址前缀或标签，label 只匹配标签，均不区分大小写
+fn address_filter(query: &AddressQuery) -> Document {
+    let mut filter = doc! { "is_active": true };
+    if let Some(search) = query.search.as_deref().filter(|s| !s.is_empty()) {
+        let escaped = escape_regex(search);
+        filter.insert(
+            "$or",
+            vec![
+                doc! { "address": { "$regex": format!("^{}", escaped), "$options": "i" } },
+                doc! { "label": { "$regex": escaped, "$options": "i" } },
+            ],
+        );
+    }
+    if let Some(label) = query.label.as_deref().filter(|s| !s.is_empty()) {
+        filter.insert(
+            "label",
+            doc! { "$regex": escape_regex(label), "$options": "i" },
+        );
+    }
+    filter
+}
+
+/// 转义正则元字符，用户输入按字面量匹配
+fn escape_regex(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 批量 upsert 的逐条结果，下标对应传入的记录
+#[derive(Debug, Default)]
+pub struct BulkUpsertResult {
+    /// 新插入的记录
+    pub inserted: Vec<usize>,
+    /// 写入失败的记录及错误信息
+    pub failed: Vec<(usize, String)>,
+}
+
 pub struct TransactionRepo {
+    database: Database,
     collection: Collection<Transaction>,
 }
 
 impl TransactionRepo {
     pub fn new(database: Database) -> Self {
         let collection = database.collection("transactions");
-        Self { collection }
+        Self {
+            database,
+            collection,
+        }
     }
 
-    pub async fn insert_transaction(&self, transaction: &Transaction) -> Result<()> {
-        self.collection.insert_one(transaction, None).await?;
-        Ok(())
+    /// 以无序 update 命令批量 upsert（按记录键匹配），一批记录一次往返；
+    /// 单条失败不影响其余记录，结果逐条返回。命令本身失败时该批记录全部计为失败
+    pub async fn upsert_many(&self, transactions: &[Transaction]) -> BulkUpsertResult {
+        let mut result = BulkUpsertResult::default();
+        for (chunk_index, chunk) in transactions.chunks(BULK_WRITE_BATCH_SIZE).enumerate() {
+            let offset = chunk_index * BULK_WRITE_BATCH_SIZE;
+            if let Err(e) = self.upsert_chunk(chunk, offset, &mut result).await {
+                result
+                    .failed
+                    .extend((offset..offset + chunk.len()).map(|index| (index, e.to_string())));
+            }
+        }
+        result.inserted.sort_unstable();
+        result
     }
 
-    pub async fn get_transactions(
+    async fn upsert_chunk(
         &self,
-        address: Option<String>,
-        limit: Option<u32>,
-        offset: Option<u32>,
-    ) -> Result<Vec<Transaction>> {
-        let mut filter = doc! {};
+        chunk: &[Transaction],
+        offset: usize,
+        result: &mut BulkUpsertResult,
+    ) -> Result<()> {
+        // 与驱动写入时一致，走 raw（非 human-readable）序列化，时间字段保持为 BSON Date
+        let updates = chunk
+            .iter()
+            .map(|transaction| {
+                let replacement = mongodb::bson::to_raw_document_buf(transaction)?.to_document()?;
+                Ok(doc! {
+                    "q": { "record_key": &transaction.record_key },
+                    "u": replacement,
+                    "upsert": true,
+                })
+            })
+            .collect::<Result<Vec<Document>>>()?;
 
-        if let Some(addr) = address {
-            filter = doc! {
-                "$or": [
-                    { "from_address": &addr },
-                    { "to_address": &addr }
-                ]
-            };
+        let reply = self
+            .database
+            .run_command(
+                doc! {
+                    "update": self.collection.name(),
+                    "updates": updates,
+                    "ordered": false,
+                },
+                None,
+            )
+            .await?;
+
+        let indices = |field: &str| -> Vec<(usize, &Document)> {
+            reply
+                .get_array(field)
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter_map(Bson::as_document)
+                        .filter_map(|entry| {
+                            let index = entry.get_i32("index").ok()?;
+                            Some((offset + index as usize, entry))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        result
+            .inserted
+            .extend(indices("upserted").into_iter().map(|(index, _)| index));
+        result
+            .failed
+            .extend(indices("writeErrors").into_iter().map(|(index, entry)| {
+                let message = entry.get_str("errmsg").unwrap_or("write error");
+                (index, message.to_string())
+            }));
+        if let Ok(error) = reply.get_document("writeConcernError") {
+            // 写入已经生效，只是未满足写关注
+            tracing::warn!("Bulk upsert write concern error: {}", error);
         }
+        Ok(())
+    }
+
+    pub async fn get_transactions(&self, query: &TransactionQuery) -> Result<Vec<Transaction>> {
+        self.get_transactions_multi(&query.addresses(), query).await
+    }
+
+    /// 查询与任一地址相关的交易（作为发送方或接收方），其余过滤条件取自 query
+    pub async fn get_transactions_multi(
+        &self,
+        addresses: &[String],
+        query: &TransactionQuery,
+    ) -> Result<Vec<Transaction>> {
+        let filter = Self::build_filter_for_addresses(addresses, query)?;
 
         let mut options = mongodb::options::FindOptions::default();
+        options.sort = Some(sort_document(query.sort.unwrap_or_default()));
+        options.projection = list_projection(query);
 
-        if let Some(limit) = limit {
+        if let Some(limit) = query.limit {
             options.limit = Some(limit as i64);
         }
 
-        if let Some(offset) = offset {
+        if let Some(offset) = query.offset {
             options.skip = Some(offset as u64);
         }
         let cursor = self.collection.find(filter, options).await?;
@@ -95,43 +286,598 @@ impl TransactionRepo {
         Ok(transactions)
     }
 
-    pub async fn get_transaction_by_signature(
+    /// 满足过滤条件的交易总数，忽略 limit/offset
+    pub async fn count_transactions(&self, query: &TransactionQuery) -> Result<u64> {
+        let filter = Self::build_filter(query)?;
+        let count = self.collection.count_documents(filter, None).await?;
+        Ok(count)
+    }
+
+    /// 返回按查询排序的 Mongo 游标，供导出时逐条读取而不整体加载到内存
+    pub async fn stream_transactions(
+        &self,
+        query: &TransactionQuery,
+    ) -> Result<mongodb::Cursor<Transaction>> {
+        let filter = Self::build_filter(query)?;
+        let options = mongodb::options::FindOptions::builder()
+            .sort(sort_document(query.sort.unwrap_or_default()))
+            .limit(query.limit.map(|limit| limit as i64))
+            .skip(query.offset.map(|offset| offset as u64))
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        Ok(cursor)
+    }
+
+    /// 返回按 slot 正序遍历全部匹配记录的游标，忽略分页参数，供批量重新处理使用
+    pub async fn stream_all(
         &self,
-        signature: &str,
-    ) -> Result<Option<Transaction>> {
-        let transaction = self
+        query: &TransactionQuery,
+    ) -> Result<mongodb::Cursor<Transaction>> {
+        let filter = Self::build_filter(query)?;
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "block_number": 1, "record_key": 1 })
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        Ok(cursor)
+    }
+
+    /// 按记录键覆盖已有记录，不存在时不插入；返回 false 表示记录不存在
+    pub async fn replace_transaction(&self, transaction: &Transaction) -> Result<bool> {
+        let result = self
             .collection
-            .find_one(doc! { "signature": signature }, None)
+            .replace_one(
+                doc! { "record_key": &transaction.record_key },
+                transaction,
+                None,
+            )
             .await?;
+        Ok(result.matched_count > 0)
+    }
+
+    /// 基于游标分页，按 timestamp、signature 倒序返回，避免大偏移量下 skip 的全量扫描
+    pub async fn get_transactions_cursor(
+        &self,
+        query: &TransactionQuery,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<TransactionPage> {
+        let limit = if limit == 0 { DEFAULT_PAGE_SIZE } else { limit };
+        let mut filter = Self::build_filter(query)?;
+
+        if let Some(cursor) = cursor {
+            let (timestamp, record_key) = decode_cursor(cursor)?;
+            let timestamp = mongodb::bson::DateTime::from_chrono(timestamp);
+            let after_cursor = doc! {
+                "$or": [
+                    { "timestamp": { "$lt": timestamp } },
+                    { "timestamp": timestamp, "record_key": { "$lt": record_key } }
+                ]
+            };
+            filter = if filter.is_empty() {
+                after_cursor
+            } else {
+                doc! { "$and": [filter, after_cursor] }
+            };
+        }
+
+        // 多取一条用于判断是否还有下一页
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "timestamp": -1, "record_key": -1 })
+            .limit(limit as i64 + 1)
+            .projection(list_projection(query))
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        let mut data: Vec<Transaction> = cursor.try_collect().await?;
+
+        let next_cursor = if data.len() > limit as usize {
+            data.truncate(limit as usize);
+            data.last()
+                .map(|tx| encode_cursor(&tx.timestamp, &tx.record_key))
+        } else {
+            None
+        };
+
+        Ok(TransactionPage { data, next_cursor })
+    }
+
+    /// 聚合统计：总数、按类型/状态计数、主链币交易总额与手续费总额
+    pub async fn aggregate_stats(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<StatsSummary> {
+        let filter = Self::build_filter(&TransactionQuery {
+            start_time,
+            end_time,
+            ..Default::default()
+        })?;
+
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! {
+                "$facet": {
+                    "totals": [
+                        {
+                            "$group": {
+                                "_id": Bson::Null,
+                                "count": { "$sum": 1 },
+                                "fees": { "$sum": "$fee" },
+                                "native_volume": {
+                                    "$sum": {
+                                        "$cond": [
+                                            { "$eq": ["$transaction_type", "native"] },
+                                            "$amount",
+                                            0
+                                        ]
+                                    }
+                                }
+                            }
+                        }
+                    ],
+                    "by_type": [
+                        { "$group": { "_id": "$transaction_type", "count": { "$sum": 1 } } }
+                    ],
+                    "by_status": [
+                        { "$group": { "_id": "$status", "count": { "$sum": 1 } } }
+                    ]
+                }
+            },
+        ];
+
+        let mut cursor = self.collection.aggregate(pipeline, None).await?;
+        let mut summary = StatsSummary::default();
+        let Some(result) = cursor.try_next().await? else {
+            return Ok(summary);
+        };
+
+        if let Some(totals) = first_document(&result, "totals") {
+            summary.total_transactions = totals.get("count").map(bson_to_f64).unwrap_or(0.0) as u64;
+            summary.total_fees = totals.get("fees").map(bson_to_f64).unwrap_or(0.0);
+            summary.total_native_volume =
+                totals.get("native_volume").map(bson_to_f64).unwrap_or(0.0);
+        }
+        summary.by_type = group_counts(&result, "by_type");
+        summary.by_status = group_counts(&result, "by_status");
+
+        Ok(summary)
+    }
+
+    /// 按天或小时分桶统计交易数量和主链币交易金额，按时间正序返回
+    pub async fn count_by_interval(
+        &self,
+        address: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        interval: TimeInterval,
+    ) -> Result<Vec<TimeBucket>> {
+        let filter = Self::build_filter(&TransactionQuery {
+            address: address.map(|a| a.to_string()),
+            start_time,
+            end_time,
+            ..Default::default()
+        })?;
+
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! {
+                "$group": {
+                    "_id": {
+                        "$dateToString": { "format": interval.date_format(), "date": "$timestamp" }
+                    },
+                    "count": { "$sum": 1 },
+                    "volume": {
+                        "$sum": {
+                            "$cond": [
+                                { "$eq": ["$transaction_type", "native"] },
+                                "$amount",
+                                0
+                            ]
+                        }
+                    }
+                }
+            },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+
+        let cursor = self.collection.aggregate(pipeline, None).await?;
+        let groups: Vec<Document> = cursor.try_collect().await?;
+        Ok(groups
+            .iter()
+            .filter_map(|group| {
+                Some(TimeBucket {
+                    date: group.get_str("_id").ok()?.to_string(),
+                    count: group.get("count").map(bson_to_f64).unwrap_or(0.0) as u64,
+                    volume: group.get("volume").map(bson_to_f64).unwrap_or(0.0),
+                })
+            })
+            .collect())
+    }
+
+    fn build_filter(query: &TransactionQuery) -> Result<Document> {
+        Self::build_filter_for_addresses(&query.addresses(), query)
+    }
+
+    fn build_filter_for_addresses(
+        addresses: &[String],
+        query: &TransactionQuery,
+    ) -> Result<Document> {
+        let mut filter = doc! {};
+
+        if addresses.len() > MAX_QUERY_ADDRESSES {
+            return Err(ScannerError::InvalidRequest(format!(
+                "Too many addresses: {} (max {})",
+                addresses.len(),
+                MAX_QUERY_ADDRESSES
+            ))
+            .into());
+        }
+        match addresses {
+            [] => {}
+            [addr] => {
+                filter.insert(
+                    "$or",
+                    vec![doc! { "from_address": addr }, doc! { "to_address": addr }],
+                );
+            }
+            _ => {
+                filter.insert(
+                    "$or",
+                    vec![
+                        doc! { "from_address": { "$in": addresses.to_vec() } },
+                        doc! { "to_address": { "$in": addresses.to_vec() } },
+                    ],
+                );
+            }
+        }
+
+        if let Some(transaction_type) = &query.transaction_type {
+            filter.insert(
+                "transaction_type",
+                mongodb::bson::to_bson(transaction_type)?,
+            );
+        } else if !query.transaction_types.is_empty() {
+            filter.insert(
+                "transaction_type",
+                doc! { "$in": mongodb::bson::to_bson(&query.transaction_types)? },
+            );
+        }
+
+        if let (Some(start), Some(end)) = (query.start_time, query.end_time) {
+            if start > end {
+                return Err(ScannerError::InvalidRequest(
+                    "start_time must not be after end_time".to_string(),
+                )
+                .into());
+            }
+        }
+        let mut time_range = doc! {};
+        if let Some(start_time) = &query.start_time {
+            time_range.insert("$gte", mongodb::bson::DateTime::from_chrono(*start_time));
+        }
+        if let Some(end_time) = &query.end_time {
+            time_range.insert("$lte", mongodb::bson::DateTime::from_chrono(*end_time));
+        }
+        if !time_range.is_empty() {
+            filter.insert("timestamp", time_range);
+        }
+
+        if let (Some(min), Some(max)) = (query.min_amount, query.max_amount) {
+            if min > max {
+                return Err(ScannerError::InvalidRequest(
+                    "min_amount must not exceed max_amount".to_string(),
+                )
+                .into());
+            }
+        }
+        let mut amount_range = doc! {};
+        if let Some(min_amount) = query.min_amount {
+            amount_range.insert("$gte", min_amount);
+        }
+        if let Some(max_amount) = query.max_amount {
+            amount_range.insert("$lte", max_amount);
+        }
+        if !amount_range.is_empty() {
+            filter.insert("amount", amount_range);
+        }
+
+        Ok(filter)
+    }
+
+    /// 按记录键或签名查询，签名对应多条记录时按记录键顺序返回第一条
+    pub async fn get_transaction_by_key(&self, key: &str) -> Result<Option<Transaction>> {
+        let options = mongodb::options::FindOneOptions::builder()
+            .sort(doc! { "record_key": 1 })
+            .build();
+        let transaction = self.collection.find_one(key_filter(key), options).await?;
 
         Ok(transaction)
     }
+
+    /// 只读取交易的 raw_data 字段。外层 None 表示交易不存在，内层 None 表示没有保存 raw_data
+    pub async fn get_raw_data(&self, key: &str) -> Result<Option<Option<serde_json::Value>>> {
+        let options = mongodb::options::FindOneOptions::builder()
+            .sort(doc! { "record_key": 1 })
+            .projection(doc! { "raw_data": 1, "_id": 0 })
+            .build();
+        let document = self
+            .collection
+            .clone_with_type::<Document>()
+            .find_one(key_filter(key), options)
+            .await?;
+
+        Ok(document.map(|document| match document.get("raw_data") {
+            None | Some(Bson::Null) => None,
+            Some(raw_data) => Some(raw_data.clone().into_relaxed_extjson()),
+        }))
+    }
+
+    /// 查询区块高度落在 [from_slot, to_slot) 内的交易，分叉回滚前用于扣减地址统计
+    pub async fn get_transactions_in_slot_range(
+        &self,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Transaction>> {
+        let cursor = self
+            .collection
+            .find(
+                doc! { "block_number": { "$gte": from_slot as i64, "$lt": to_slot as i64 } },
+                None,
+            )
+            .await?;
+        let transactions: Vec<Transaction> = cursor.try_collect().await?;
+
+        Ok(transactions)
+    }
+
+    /// 删除区块高度落在 [from_slot, to_slot) 内的交易，返回删除数量
+    pub async fn delete_transactions_in_slot_range(
+        &self,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<u64> {
+        let result = self
+            .collection
+            .delete_many(
+                doc! { "block_number": { "$gte": from_slot as i64, "$lt": to_slot as i64 } },
+                None,
+            )
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+}
+
+/// 匹配记录键或签名
+fn key_filter(key: &str) -> Document {
+    doc! { "$or": [{ "record_key": key }, { "signature": key }] }
+}
+
+fn first_document<'a>(result: &'a Document, key: &str) -> Option<&'a Document> {
+    result
+        .get_array(key)
+        .ok()?
+        .first()
+        .and_then(|b| b.as_document())
+}
+
+fn group_counts(result: &Document, key: &str) -> std::collections::HashMap<String, u64> {
+    result
+        .get_array(key)
+        .map(|groups| {
+            groups
+                .iter()
+                .filter_map(|b| b.as_document())
+                .filter_map(|group| {
+                    let name = group.get_str("_id").ok()?.to_string();
+                    let count = group.get("count").map(bson_to_f64).unwrap_or(0.0) as u64;
+                    Some((name, count))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn bson_to_f64(value: &Bson) -> f64 {
+    match value {
+        Bson::Int32(v) => *v as f64,
+        Bson::Int64(v) => *v as f64,
+        Bson::Double(v) => *v,
+        _ => 0.0,
+    }
+}
+
+/// 列表查询默认去掉 raw_data，反序列化后为 None
+fn list_projection(query: &TransactionQuery) -> Option<Document> {
+    (!query.include_raw).then(|| doc! { "raw_data": 0 })
+}
+
+/// 以唯一的 record_key 作为次级排序保证结果稳定，与 (timestamp, record_key)、(amount, record_key) 索引对应
+fn sort_document(sort: TransactionSort) -> Document {
+    match sort {
+        TransactionSort::TimestampAsc => doc! { "timestamp": 1, "record_key": 1 },
+        TransactionSort::TimestampDesc => doc! { "timestamp": -1, "record_key": -1 },
+        TransactionSort::AmountDesc => doc! { "amount": -1, "record_key": -1 },
+    }
+}
+
+pub(crate) fn encode_cursor(timestamp: &DateTime<Utc>, record_key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}|{}", timestamp.to_rfc3339(), record_key))
+}
+
+fn invalid_cursor() -> ScannerError {
+    ScannerError::InvalidRequest("Invalid cursor".to_string())
+}
+
+pub(crate) fn decode_cursor(cursor: &str) -> Result<(DateTime<Utc>, String)> {
+    let raw = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| invalid_cursor())?;
+    let raw = String::from_utf8(raw).map_err(|_| invalid_cursor())?;
+    let (timestamp, record_key) = raw.split_once('|').ok_or_else(invalid_cursor)?;
+    let timestamp = DateTime::parse_from_rfc3339(timestamp)
+        .map_err(|_| invalid_cursor())?
+        .with_timezone(&Utc);
+
+    Ok((timestamp, record_key.to_string()))
 }
 
 pub struct ScanStatusRepo {
     collection: Collection<ScanStatus>,
+    /// 分片部署时每个分片有独立的扫描状态文档
+    id: String,
 }
 
 impl ScanStatusRepo {
-    pub fn new(database: Database) -> Self {
+    pub fn new(database: Database, id: &str) -> Self {
         let collection = database.collection("scan_status");
-        Self { collection }
+        Self {
+            collection,
+            id: id.to_string(),
+        }
     }
 
     pub async fn get_scan_status(&self) -> Result<Option<ScanStatus>> {
         let status = self
             .collection
-            .find_one(doc! { "id": "scan_status" }, None)
+            .find_one(doc! { "id": &self.id }, None)
             .await?;
 
         Ok(status)
     }
 
+    /// 写入扫描状态，last_scanned_block 使用 $max 只增不减，
+    /// 多个任务乱序写入时较小的 slot 不会覆盖已落库的检查点
     pub async fn update_scan_status(&self, status: &ScanStatus) -> Result<()> {
+        let update = doc! {
+            "$max": { "last_scanned_block": status.last_scanned_block as i64 },
+            "$set": {
+                "last_scan_time": mongodb::bson::DateTime::from_chrono(status.last_scan_time),
+                "total_transactions_scanned": status.total_transactions_scanned as i64,
+                "is_scanning": status.is_scanning,
+            },
+        };
+        self.collection
+            .update_one(
+                doc! { "id": &status.id },
+                update,
+                mongodb::options::UpdateOptions::builder()
+                    .upsert(true)
+                    .build(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 直接覆盖检查点（可回退），返回覆盖前的值；文档不存在时新建
+    pub async fn set_checkpoint(&self, slot: u64) -> Result<Option<u64>> {
+        let update = doc! {
+            "$set": {
+                "last_scanned_block": slot as i64,
+                "last_scan_time": mongodb::bson::DateTime::now(),
+            },
+            "$setOnInsert": {
+                "total_transactions_scanned": 0i64,
+                "is_scanning": false,
+            },
+        };
+        let previous = self
+            .collection
+            .find_one_and_update(
+                doc! { "id": &self.id },
+                update,
+                mongodb::options::FindOneAndUpdateOptions::builder()
+                    .upsert(true)
+                    .return_document(mongodb::options::ReturnDocument::Before)
+                    .build(),
+            )
+            .await?;
+
+        Ok(previous.map(|status| status.last_scanned_block))
+    }
+}
+
+pub struct ScannerLockRepo {
+    collection: Collection<ScannerLock>,
+}
+
+impl ScannerLockRepo {
+    pub fn new(database: Database) -> Self {
+        let collection = database.collection("scanner_lock");
+        Self { collection }
+    }
+
+    /// 获取或续期租约：锁不存在、已过期或本就由 holder 持有时成功，返回 false 表示被其他实例持有
+    pub async fn try_acquire(&self, id: &str, holder: &str, ttl: chrono::Duration) -> Result<bool> {
+        let now = Utc::now();
+        let filter = doc! {
+            "_id": id,
+            "$or": [
+                { "holder": holder },
+                { "expires_at": { "$lt": mongodb::bson::DateTime::from_chrono(now) } },
+            ],
+        };
+        let update = doc! {
+            "$set": {
+                "holder": holder,
+                "expires_at": mongodb::bson::DateTime::from_chrono(now + ttl),
+            },
+        };
+        let options = mongodb::options::UpdateOptions::builder()
+            .upsert(true)
+            .build();
+        match self.collection.update_one(filter, update, options).await {
+            Ok(_) => Ok(true),
+            // 锁由其他实例持有时过滤条件不匹配，upsert 插入同一 _id 触发重复键错误
+            Err(e) => match e.kind.as_ref() {
+                ErrorKind::Write(mongodb::error::WriteFailure::WriteError(we))
+                    if we.code == DUPLICATE_KEY_ERROR =>
+                {
+                    Ok(false)
+                }
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    /// 主动释放租约，待命实例下次检查时即可接管
+    pub async fn release(&self, id: &str, holder: &str) -> Result<()> {
+        self.collection
+            .update_one(
+                doc! { "_id": id, "holder": holder },
+                doc! { "$set": { "expires_at": mongodb::bson::DateTime::now() } },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct BlockHashRepo {
+    collection: Collection<BlockHash>,
+}
+
+impl BlockHashRepo {
+    pub fn new(database: Database) -> Self {
+        let collection = database.collection("block_hashes");
+        Self { collection }
+    }
+
+    pub async fn get_block_hash(&self, slot: u64) -> Result<Option<BlockHash>> {
+        let block_hash = self
+            .collection
+            .find_one(doc! { "slot": slot as i64 }, None)
+            .await?;
+
+        Ok(block_hash)
+    }
+
+    pub async fn upsert_block_hash(&self, block_hash: &BlockHash) -> Result<()> {
         self.collection
             .replace_one(
-                doc! { "id": "scan_status" },
-                status,
+                doc! { "slot": block_hash.slot as i64 },
+                block_hash,
                 mongodb::options::ReplaceOptions::builder()
                     .upsert(true)
                     .build(),
@@ -140,4 +886,175 @@ impl ScanStatusRepo {
 
         Ok(())
     }
+
+    pub async fn delete_block_hashes_in_slot_range(
+        &self,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<()> {
+        self.collection
+            .delete_many(
+                doc! { "slot": { "$gte": from_slot as i64, "$lt": to_slot as i64 } },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct DeadLetterRepo {
+    collection: Collection<KafkaDeadLetter>,
+}
+
+impl DeadLetterRepo {
+    pub fn new(database: Database) -> Self {
+        let collection = database.collection("kafka_dead_letter");
+        Self { collection }
+    }
+
+    pub async fn insert_dead_letter(&self, dead_letter: &KafkaDeadLetter) -> Result<()> {
+        self.collection.insert_one(dead_letter, None).await?;
+        Ok(())
+    }
+
+    /// 按失败时间从早到晚取出一批死信
+    pub async fn get_dead_letters(&self, limit: u32) -> Result<Vec<KafkaDeadLetter>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "failed_at": 1, "_id": 1 })
+            .limit(limit as i64)
+            .build();
+        let cursor = self.collection.find(None, options).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    pub async fn delete_dead_letter(&self, id: mongodb::bson::oid::ObjectId) -> Result<()> {
+        self.collection.delete_one(doc! { "_id": id }, None).await?;
+        Ok(())
+    }
+
+    pub async fn count_dead_letters(&self) -> Result<u64> {
+        let count = self.collection.count_documents(None, None).await?;
+        Ok(count)
+    }
+}
+
+pub struct MissedSlotsRepo {
+    collection: Collection<MissedSlot>,
+}
+
+impl MissedSlotsRepo {
+    pub fn new(database: Database) -> Self {
+        let collection = database.collection("missed_slots");
+        Self { collection }
+    }
+
+    /// 记录扫描失败的 slot，重复记录时累加失败次数
+    pub async fn record_missed_slot(&self, slot: u64, error: &str) -> Result<()> {
+        let now = mongodb::bson::DateTime::now();
+        self.collection
+            .update_one(
+                doc! { "slot": slot as i64 },
+                doc! {
+                    "$set": { "error": error, "last_failed_at": now },
+                    "$inc": { "attempts": 1 },
+                    "$setOnInsert": { "first_failed_at": now }
+                },
+                mongodb::options::UpdateOptions::builder()
+                    .upsert(true)
+                    .build(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// 按 slot 升序取出待补扫的 slot，shard_count 大于 1 时只返回 shard_index 分片负责的 slot
+    pub async fn get_missed_slots(
+        &self,
+        limit: i64,
+        shard_index: u64,
+        shard_count: u64,
+    ) -> Result<Vec<MissedSlot>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "slot": 1 })
+            .limit(limit)
+            .build();
+        let filter = if shard_count > 1 {
+            doc! { "slot": { "$mod": [shard_count as i64, shard_index as i64] } }
+        } else {
+            doc! {}
+        };
+        let cursor = self.collection.find(filter, options).await?;
+        let slots: Vec<MissedSlot> = cursor.try_collect().await?;
+
+        Ok(slots)
+    }
+
+    pub async fn remove_missed_slot(&self, slot: u64) -> Result<()> {
+        self.collection
+            .delete_one(doc! { "slot": slot as i64 }, None)
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct AddressStatsRepo {
+    collection: Collection<AddressStats>,
+}
+
+impl AddressStatsRepo {
+    pub fn new(database: Database) -> Self {
+        let collection = database.collection("address_stats");
+        Self { collection }
+    }
+
+    /// 用更新管道原子累加统计，传入负值用于分叉回滚；upsert 为 false 时只更新已有记录。
+    /// 结果不低于 0：回滚统计集合建立之前入库的交易时，扣减会超过已有计数，tx_count 为无符号数，负值无法读出
+    pub async fn increment(
+        &self,
+        address: &str,
+        received: f64,
+        sent: f64,
+        tx_count: i64,
+        upsert: bool,
+    ) -> Result<()> {
+        // 0 与增量同类型，截断后字段类型不变
+        let clamped_add = |field: &str, delta: Bson, zero: Bson| {
+            let current = doc! { "$ifNull": [format!("${}", field), zero.clone()] };
+            doc! { "$max": [zero, { "$add": [current, delta] }] }
+        };
+        let total_received =
+            clamped_add("total_received", Bson::Double(received), Bson::Double(0.0));
+        let total_sent = clamped_add("total_sent", Bson::Double(sent), Bson::Double(0.0));
+        let tx_count = clamped_add("tx_count", Bson::Int64(tx_count), Bson::Int64(0));
+        self.collection
+            .update_one(
+                doc! { "address": address },
+                vec![doc! {
+                    "$set": {
+                        "total_received": total_received,
+                        "total_sent": total_sent,
+                        "tx_count": tx_count,
+                        "updated_at": mongodb::bson::DateTime::now()
+                    }
+                }],
+                mongodb::options::UpdateOptions::builder()
+                    .upsert(upsert)
+                    .build(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_stats(&self, address: &str) -> Result<Option<AddressStats>> {
+        let stats = self
+            .collection
+            .find_one(doc! { "address": address }, None)
+            .await?;
+
+        Ok(stats)
+    }
 }
@@ -0,0 +1,180 @@
+use super::*;
+use chrono::{Duration, TimeZone, Utc};
+use mongodb::bson::{doc, Document};
+
+use crate::models::tests::sample_transaction;
+use crate::models::{AddressQuery, ScanStatus, TransactionQuery};
+
+// 需要本地 MongoDB：MONGODB_URI=mongodb://localhost:27017 cargo test -- --ignored
+async fn test_db() -> Database {
+    let uri =
+        std::env::var("MONGODB_URI").unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
+    let client = mongodb::Client::with_uri_str(&uri).await.unwrap();
+    client.database("solana_scanner_test")
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_deactivated_address_can_be_reactivated() {
+    let database = test_db().await;
+    let address = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+    let raw = database.collection::<Document>("wallet_addresses");
+    raw.delete_many(doc! { "address": address }, None)
+        .await
+        .unwrap();
+
+    let repo = WalletAddressRepo::new(database.clone());
+    repo.insert_address(address, Some("old")).await.unwrap();
+    assert!(repo.deactivate_address(address).await.unwrap());
+    assert!(!repo.deactivate_address(address).await.unwrap());
+    assert!(!is_active(&repo, address).await);
+
+    repo.insert_address(address, Some("new")).await.unwrap();
+    let active = repo.get_all_active_addresses().await.unwrap();
+    let wallet = active.iter().find(|w| w.address == address).unwrap();
+    assert_eq!(wallet.label.as_deref(), Some("new"));
+    assert_eq!(
+        raw.count_documents(doc! { "address": address }, None)
+            .await
+            .unwrap(),
+        1
+    );
+
+    raw.delete_many(doc! { "address": address }, None)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_addresses_searchable_by_prefix_and_label() {
+    let database = test_db().await;
+    let address = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+    let raw = database.collection::<Document>("wallet_addresses");
+    raw.delete_many(doc! { "address": address }, None)
+        .await
+        .unwrap();
+
+    let repo = WalletAddressRepo::new(database.clone());
+    repo.insert_address(address, Some("Treasury (Hot)"))
+        .await
+        .unwrap();
+
+    let by_prefix = AddressQuery {
+        search: Some("9wzdx".to_string()),
+        ..Default::default()
+    };
+    let found = repo.get_active_addresses_paged(&by_prefix).await.unwrap();
+    assert!(found.iter().any(|w| w.address == address));
+
+    // 正则元字符按字面量匹配
+    let by_label = AddressQuery {
+        label: Some("(hot)".to_string()),
+        ..Default::default()
+    };
+    assert!(repo.count_addresses(&by_label).await.unwrap() >= 1);
+    let not_prefix = AddressQuery {
+        search: Some("WzDX".to_string()),
+        ..Default::default()
+    };
+    let found = repo.get_active_addresses_paged(&not_prefix).await.unwrap();
+    assert!(!found.iter().any(|w| w.address == address));
+
+    raw.delete_many(doc! { "address": address }, None)
+        .await
+        .unwrap();
+}
+
+async fn is_active(repo: &WalletAddressRepo, address: &str) -> bool {
+    repo.get_all_active_addresses()
+        .await
+        .unwrap()
+        .iter()
+        .any(|w| w.address == address)
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_transactions_queryable_by_time_range() {
+    let database = test_db().await;
+    let from_address = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+    let raw = database.collection::<Document>("transactions");
+    raw.delete_many(doc! { "from_address": from_address }, None)
+        .await
+        .unwrap();
+
+    let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let repo = TransactionRepo::new(database.clone());
+    let transactions: Vec<_> = (0..3)
+        .map(|day| {
+            let mut transaction = sample_transaction();
+            transaction.signature = format!("time-range-test-{}", day);
+            transaction.record_key = transaction.signature.clone();
+            transaction.block_number = day as u64;
+            transaction.from_address = from_address.to_string();
+            transaction.timestamp = base + Duration::days(day);
+            transaction
+        })
+        .collect();
+    let result = repo.upsert_many(&transactions).await;
+    assert_eq!(result.inserted, vec![0, 1, 2]);
+    assert!(result.failed.is_empty());
+    // 重复写入只更新，不算新插入
+    assert!(repo.upsert_many(&transactions).await.inserted.is_empty());
+
+    let query = TransactionQuery {
+        address: Some(from_address.to_string()),
+        start_time: Some(base + Duration::hours(12)),
+        end_time: Some(base + Duration::hours(36)),
+        ..Default::default()
+    };
+    let found = repo.get_transactions(&query).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].signature, "time-range-test-1");
+
+    raw.delete_many(doc! { "from_address": from_address }, None)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_scan_status_checkpoint_does_not_regress() {
+    let database = test_db().await;
+    let raw = database.collection::<Document>("scan_status");
+    raw.delete_many(doc! {}, None).await.unwrap();
+
+    // 模拟乱序完成：较大的 slot 先落库，较小的 slot 后写入
+    let repo = ScanStatusRepo::new(database.clone(), ScanStatus::DEFAULT_ID);
+    repo.update_scan_status(&ScanStatus::new(200))
+        .await
+        .unwrap();
+    repo.update_scan_status(&ScanStatus::new(150))
+        .await
+        .unwrap();
+    let status = repo.get_scan_status().await.unwrap().unwrap();
+    assert_eq!(status.last_scanned_block, 200);
+
+    repo.update_scan_status(&ScanStatus::new(250))
+        .await
+        .unwrap();
+    let status = repo.get_scan_status().await.unwrap().unwrap();
+    assert_eq!(status.last_scanned_block, 250);
+
+    raw.delete_many(doc! {}, None).await.unwrap();
+}
+
+#[test]
+fn test_cursor_round_trips_record_key() {
+    let timestamp = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let cursor = repos::encode_cursor(
+        &timestamp,
+        "5w6TpwP8pPhQ2EeFF3N7PQHQbmVjFduJR5WcKjdqSPM:2.1",
+    );
+    let (decoded_timestamp, record_key) = repos::decode_cursor(&cursor).unwrap();
+    assert_eq!(decoded_timestamp, timestamp);
+    assert_eq!(
+        record_key,
+        "5w6TpwP8pPhQ2EeFF3N7PQHQbmVjFduJR5WcKjdqSPM:2.1"
+    );
+}
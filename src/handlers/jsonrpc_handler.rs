@@ -0,0 +1,203 @@
+use axum::{
+    body::Bytes,
+    extract::{Extension, Json, State},
+    http::HeaderMap,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::handlers::rpc_handler::has_valid_api_key;
+use crate::models::TransactionQuery;
+use crate::services::blockchain::BlockchainScanner;
+use crate::utils::error::ScannerError;
+
+// JSON-RPC 2.0 标准错误码
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+// 业务错误码，使用 -32000 ~ -32099 的服务端保留区间
+const UNAUTHORIZED: i64 = -32001;
+const NOT_FOUND: i64 = -32004;
+const CONFLICT: i64 = -32009;
+const RATE_LIMITED: i64 = -32029;
+
+#[derive(Deserialize)]
+struct JsonRpcRequest {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    #[serde(default)]
+    id: Value,
+}
+
+#[derive(Deserialize)]
+struct AddressParams {
+    address: String,
+    label: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+impl JsonRpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ScannerError> for JsonRpcError {
+    fn from(error: ScannerError) -> Self {
+        let code = match &error {
+            ScannerError::InvalidAddress(_) | ScannerError::InvalidRequest(_) => INVALID_PARAMS,
+            ScannerError::TransactionNotFound(_) | ScannerError::AddressNotFound(_) => NOT_FOUND,
+            ScannerError::Conflict(_) | ScannerError::LimitExceeded(_) => CONFLICT,
+            ScannerError::RateLimitExceeded => RATE_LIMITED,
+            ScannerError::Unauthorized => UNAUTHORIZED,
+            _ => INTERNAL_ERROR,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<anyhow::Error> for JsonRpcError {
+    fn from(error: anyhow::Error) -> Self {
+        ScannerError::from(error).into()
+    }
+}
+
+/// POST /rpc，支持单个请求和批量请求
+pub async fn handle_jsonrpc(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Extension(api_keys): Extension<Arc<Vec<String>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Json<Value> {
+    let authorized = has_valid_api_key(&api_keys, &headers);
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = JsonRpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+            return Json(error_response(Value::Null, error));
+        }
+    };
+
+    match request {
+        Value::Array(batch) if !batch.is_empty() => {
+            let mut responses = Vec::with_capacity(batch.len());
+            for request in batch {
+                responses.push(handle_request(&scanner, request, authorized).await);
+            }
+            Json(Value::Array(responses))
+        }
+        request => Json(handle_request(&scanner, request, authorized).await),
+    }
+}
+
+async fn handle_request(
+    scanner: &Arc<RwLock<BlockchainScanner>>,
+    request: Value,
+    authorized: bool,
+) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let request: JsonRpcRequest = match serde_json::from_value(request) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = JsonRpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e));
+            return error_response(id, error);
+        }
+    };
+    if request.jsonrpc != "2.0" {
+        let error = JsonRpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+        return error_response(request.id, error);
+    }
+
+    match dispatch(scanner, &request.method, request.params, authorized).await {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": request.id }),
+        Err(error) => error_response(request.id, error),
+    }
+}
+
+async fn dispatch(
+    scanner: &Arc<RwLock<BlockchainScanner>>,
+    method: &str,
+    params: Value,
+    authorized: bool,
+) -> Result<Value, JsonRpcError> {
+    match method {
+        "getTransactions" => {
+            let query: TransactionQuery = parse_params(params)?;
+            let transactions = scanner.read().await.get_transactions(query).await?;
+            to_result(&transactions)
+        }
+        "getAddresses" => {
+            let addresses = scanner.read().await.get_watched_addresses().await?;
+            to_result(&addresses)
+        }
+        "addAddress" => {
+            require_authorized(authorized)?;
+            let params: AddressParams = parse_params(params)?;
+            scanner
+                .read()
+                .await
+                .add_watched_address(params.address, params.label)
+                .await?;
+            Ok(Value::Bool(true))
+        }
+        "removeAddress" => {
+            require_authorized(authorized)?;
+            let params: AddressParams = parse_params(params)?;
+            let removed = scanner
+                .read()
+                .await
+                .remove_watched_address(params.address.clone())
+                .await?;
+            if !removed {
+                return Err(ScannerError::AddressNotFound(params.address).into());
+            }
+            Ok(Value::Bool(true))
+        }
+        _ => Err(JsonRpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+/// params 只支持按名称传参，缺省时视为空对象
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, JsonRpcError> {
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        params => params,
+    };
+    serde_json::from_value(params)
+        .map_err(|e| JsonRpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn require_authorized(authorized: bool) -> Result<(), JsonRpcError> {
+    if authorized {
+        Ok(())
+    } else {
+        Err(ScannerError::Unauthorized.into())
+    }
+}
+
+fn to_result<T: Serialize>(value: &T) -> Result<Value, JsonRpcError> {
+    serde_json::to_value(value).map_err(|e| JsonRpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+fn error_response(id: Value, error: JsonRpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "error": error, "id": id })
+}
@@ -1,2 +1,141 @@
+use axum::extract::FromRef;
+use axum::Router;
+use axum_server::accept::Accept;
+use axum_server::Handle;
+use futures::future::BoxFuture;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::RwLock;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::AppConfig;
+use crate::services::blockchain::BlockchainScanner;
+use websocket_handler::WsState;
+
+pub mod jsonrpc_handler;
 pub mod rpc_handler;
 pub mod websocket_handler;
+
+/// RPC 与 WebSocket 路由共享的状态，单端口模式下两组路由合并到同一个 Router；
+/// handler 仍按各自需要的部分提取（State<Arc<RwLock<BlockchainScanner>>> / State<WsState>）
+#[derive(Clone)]
+pub struct AppState {
+    pub scanner: Arc<RwLock<BlockchainScanner>>,
+    pub ws: WsState,
+}
+
+impl FromRef<AppState> for Arc<RwLock<BlockchainScanner>> {
+    fn from_ref(state: &AppState) -> Self {
+        state.scanner.clone()
+    }
+}
+
+impl FromRef<AppState> for WsState {
+    fn from_ref(state: &AppState) -> Self {
+        state.ws.clone()
+    }
+}
+
+// 收到退出信号后等待进行中的请求完成的最长时间
+const TLS_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// axum-server 的 TLS acceptor：TCP 连接建立后先完成 rustls 握手，再交给 HTTP 服务
+#[derive(Clone)]
+pub struct TlsAcceptor(tokio_rustls::TlsAcceptor);
+
+impl<I, S> Accept<I, S> for TlsAcceptor
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = TlsStream<I>;
+    type Service = S;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let acceptor = self.0.clone();
+        Box::pin(async move {
+            let stream = acceptor.accept(stream).await?;
+            Ok((stream, service))
+        })
+    }
+}
+
+/// 配置了证书和私钥时加载 TLS 配置，否则返回 None（明文服务）
+pub fn load_tls(config: &AppConfig) -> io::Result<Option<TlsAcceptor>> {
+    let (Some(cert_path), Some(key_path)) = (&config.tls_cert_path, &config.tls_key_path) else {
+        return Ok(None);
+    };
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))?
+        .into_iter()
+        .map(Certificate)
+        .collect::<Vec<_>>();
+    if certs.is_empty() {
+        return Err(invalid_tls(format!(
+            "no certificate found in {}",
+            cert_path
+        )));
+    }
+    let key = read_private_key(key_path)?;
+
+    let server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid_tls(e.to_string()))?;
+    Ok(Some(TlsAcceptor(tokio_rustls::TlsAcceptor::from(
+        Arc::new(server_config),
+    ))))
+}
+
+/// 读取 PEM 中的第一个私钥，支持 PKCS#8、PKCS#1（RSA）和 SEC1（EC）格式
+fn read_private_key(path: &str) -> io::Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => continue,
+        }
+    }
+    Err(invalid_tls(format!("no private key found in {}", path)))
+}
+
+fn invalid_tls(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 启动 HTTP 服务直到收到退出信号：有 TLS 配置时使用 axum-server + TlsAcceptor，否则沿用 axum::serve
+pub(crate) async fn serve(
+    app: Router,
+    addr: SocketAddr,
+    tls: Option<TlsAcceptor>,
+    shutdown: CancellationToken,
+) -> io::Result<()> {
+    let Some(tls) = tls else {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        return axum::serve(listener, app)
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await;
+    };
+
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.cancelled().await;
+        shutdown_handle.graceful_shutdown(Some(TLS_SHUTDOWN_GRACE));
+    });
+    axum_server::bind(addr)
+        .acceptor(tls)
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await
+}
@@ -1,22 +1,43 @@
 use axum::{
-    extract::{Json, Query, State},
-    response::IntoResponse,
+    body::{Body, Bytes},
+    extract::{Extension, Json, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use metrics_exporter_prometheus::PrometheusHandle;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, info_span, Instrument};
 
-use crate::models::{RpcResponse, Transaction};
+use crate::handlers::{self, jsonrpc_handler, websocket_handler, AppState, TlsAcceptor};
+use crate::models::{
+    AddressList, AddressQuery, AddressReloadResult, AddressStats, Capabilities, HealthDetail,
+    KafkaReplayQuery, KafkaReplayResult, RpcResponse, ServiceInfo, StatsSummary, TimeBucket,
+    TimeInterval, Transaction, TransactionList, TransactionPage, TransactionQuery,
+};
 use crate::services::blockchain::BlockchainScanner;
+use crate::utils::error::ScannerError;
+
+#[derive(Deserialize)]
+struct StatsQuery {
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+}
 
 #[derive(Deserialize)]
-struct TransactionQuery {
+struct TimeseriesQuery {
     address: Option<String>,
-    limit: Option<u32>,
-    offset: Option<u32>,
+    interval: Option<TimeInterval>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
@@ -25,90 +46,445 @@ struct AddAddressRequest {
     label: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct BackfillRequest {
+    from: u64,
+    to: u64,
+}
+
+#[derive(Deserialize)]
+struct CheckpointRequest {
+    slot: u64,
+}
+
 #[derive(Serialize)]
-struct AddressResponse {
-    addresses: Vec<String>,
+struct CheckpointResponse {
+    previous: Option<u64>,
+    slot: u64,
 }
 
-pub async fn start_rpc_server(scanner: Arc<RwLock<BlockchainScanner>>) {
-    let app = Router::new()
+pub async fn start_rpc_server(
+    state: AppState,
+    api_keys: Vec<String>,
+    metrics_handle: PrometheusHandle,
+    addr: SocketAddr,
+    tls: Option<TlsAcceptor>,
+    single_port: bool,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    // 会修改扫描状态的接口需要校验 API Key
+    let api_keys = Arc::new(api_keys);
+    let auth = middleware::from_fn_with_state(api_keys.clone(), require_api_key);
+
+    let mut app = Router::new()
         .route("/health", get(health_check))
+        .route("/ready", get(ready_check))
+        .route(
+            "/metrics",
+            get(move || std::future::ready(metrics_handle.render())),
+        )
+        .route("/info", get(get_info))
+        .route("/capabilities", get(get_capabilities))
         .route("/transactions", get(get_transactions))
+        .route("/transactions/page", get(get_transactions_page))
+        .route("/transactions/export", get(export_transactions))
+        .route("/transactions/timeseries", get(get_timeseries))
+        .route("/transactions/:signature", get(get_transaction))
+        .route("/transactions/:signature/raw", get(get_transaction_raw))
+        .route("/stats", get(get_stats))
+        .route("/backfill", post(start_backfill).route_layer(auth.clone()))
+        .route(
+            "/backfill/cancel",
+            post(cancel_backfill).route_layer(auth.clone()),
+        )
+        .route(
+            "/admin/checkpoint",
+            post(set_checkpoint).route_layer(auth.clone()),
+        )
+        .route(
+            "/admin/kafka/replay",
+            post(replay_dead_letters).route_layer(auth.clone()),
+        )
         .route("/addresses", get(get_addresses))
-        .route("/addresses", post(add_address))
-        .route("/addresses/:address", axum::routing::delete(remove_address))
-        .with_state(scanner);
+        .route("/addresses", post(add_address).route_layer(auth.clone()))
+        .route(
+            "/addresses/reload",
+            post(reload_addresses).route_layer(auth.clone()),
+        )
+        .route(
+            "/addresses/:address",
+            axum::routing::delete(remove_address).route_layer(auth),
+        )
+        .route("/addresses/:address/stats", get(get_address_stats))
+        // JSON-RPC 2.0 入口，写操作方法在内部单独校验 API Key
+        .route(
+            "/rpc",
+            post(jsonrpc_handler::handle_jsonrpc).layer(Extension(api_keys)),
+        );
+    // 单端口模式下 WebSocket 的 /ws 也挂在 RPC 服务上
+    if single_port {
+        app = app.merge(websocket_handler::routes());
+    }
+    let app = app
+        .layer(middleware::from_fn(propagate_request_id))
+        .with_state(state);
 
-    let addr: std::net::SocketAddr = "0.0.0.0:8080".parse().unwrap();
-    info!("RPC server listening on {}", addr);
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!("RPC server listening on {}://{}", scheme, addr);
+    if single_port {
+        let ws_scheme = if tls.is_some() { "wss" } else { "ws" };
+        info!(
+            "WebSocket served on {}://{}/ws (single port)",
+            ws_scheme, addr
+        );
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    handlers::serve(app, addr, tls, shutdown).await
 }
 
-async fn health_check() -> impl IntoResponse {
-    Json(RpcResponse::success("healthy"))
+type ApiResult<T> = Result<Json<RpcResponse<T>>, ScannerError>;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 沿用客户端传入的 X-Request-Id，没有（或不合法）时生成新的；
+/// 处理请求期间的日志都位于带 request_id 的 span 中，并在响应头中返回该 id
+pub(crate) async fn propagate_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let span = info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+async fn require_api_key(
+    State(api_keys): State<Arc<Vec<String>>>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Result<Response, ScannerError> {
+    if !has_valid_api_key(&api_keys, &headers) {
+        return Err(ScannerError::Unauthorized);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 未配置 API Key 时始终通过，否则要求 X-API-Key 与任一配置值一致
+pub(crate) fn has_valid_api_key(api_keys: &[String], headers: &HeaderMap) -> bool {
+    if api_keys.is_empty() {
+        return true;
+    }
+    headers
+        .get("x-api-key")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|key| api_keys.iter().any(|k| k == key))
+}
+
+async fn health_check(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+) -> (StatusCode, Json<RpcResponse<HealthDetail>>) {
+    let detail = scanner.read().await.health().await;
+    let status = if detail.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let mut response = RpcResponse::success(detail);
+    if status != StatusCode::OK {
+        response.success = false;
+        response.error = Some("Service unhealthy".to_string());
+    }
+    (status, Json(response))
+}
+
+/// 追上链头之前返回 503，供编排系统判断是否接入流量
+async fn ready_check(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+) -> (StatusCode, Json<RpcResponse<bool>>) {
+    let caught_up = scanner.read().await.is_caught_up();
+    let mut response = RpcResponse::success(caught_up);
+    if !caught_up {
+        response.success = false;
+        response.error = Some("Scanner is still catching up".to_string());
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+    }
+    (StatusCode::OK, Json(response))
+}
+
+async fn get_capabilities(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+) -> ApiResult<Capabilities> {
+    let capabilities = scanner.read().await.capabilities();
+    Ok(Json(RpcResponse::success(capabilities)))
+}
+
+async fn get_info(State(scanner): State<Arc<RwLock<BlockchainScanner>>>) -> ApiResult<ServiceInfo> {
+    let info = scanner.read().await.info().await;
+    Ok(Json(RpcResponse::success(info)))
 }
 
 async fn get_transactions(
     State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
     Query(query): Query<TransactionQuery>,
-) -> impl IntoResponse {
-    match scanner
+) -> ApiResult<TransactionList> {
+    let transactions = scanner.read().await.get_transactions(query).await?;
+    Ok(Json(RpcResponse::success(transactions)))
+}
+
+const CSV_HEADER: [&str; 13] = [
+    "signature",
+    "slot",
+    "type",
+    "from",
+    "to",
+    "amount",
+    "amount_raw",
+    "decimals",
+    "token_mint",
+    "token_symbol",
+    "fee",
+    "timestamp",
+    "status",
+];
+
+/// 以 text/csv 流式导出，Mongo 游标逐条写入响应体
+async fn export_transactions(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Query(query): Query<TransactionQuery>,
+) -> Result<Response, ScannerError> {
+    let cursor = scanner.read().await.export_transactions(query).await?;
+
+    let header = stream::once(async { csv_line(CSV_HEADER) });
+    let rows = cursor.map(|result| {
+        let tx = result?;
+        csv_line([
+            tx.signature,
+            tx.block_number.to_string(),
+            tx.transaction_type.as_str().to_string(),
+            tx.from_address,
+            tx.to_address.unwrap_or_default(),
+            tx.amount.to_string(),
+            tx.amount_raw.to_string(),
+            tx.decimals.to_string(),
+            tx.token_mint.unwrap_or_default(),
+            tx.token_symbol.unwrap_or_default(),
+            tx.fee.to_string(),
+            tx.timestamp.to_rfc3339(),
+            tx.status.as_str().to_string(),
+        ])
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"transactions.csv\"",
+            ),
+        ],
+        Body::from_stream(header.chain(rows)),
+    )
+        .into_response())
+}
+
+fn csv_line<I, T>(record: I) -> anyhow::Result<Bytes>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(record)?;
+    let line = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(Bytes::from(line))
+}
+
+async fn get_transactions_page(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Query(query): Query<TransactionQuery>,
+) -> ApiResult<TransactionPage> {
+    let page = scanner.read().await.get_transactions_page(query).await?;
+    Ok(Json(RpcResponse::success(page)))
+}
+
+async fn get_transaction(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    axum::extract::Path(signature): axum::extract::Path<String>,
+) -> ApiResult<Transaction> {
+    let transaction = scanner.read().await.get_transaction(&signature).await?;
+    Ok(Json(RpcResponse::success(transaction)))
+}
+
+async fn get_transaction_raw(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    axum::extract::Path(signature): axum::extract::Path<String>,
+) -> ApiResult<serde_json::Value> {
+    let raw_data = scanner.read().await.get_transaction_raw(&signature).await?;
+    Ok(Json(RpcResponse::success(raw_data)))
+}
+
+async fn get_stats(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Query(query): Query<StatsQuery>,
+) -> ApiResult<StatsSummary> {
+    let stats = scanner
         .read()
         .await
-        .get_transactions(query.address, query.limit, query.offset)
+        .get_stats(query.start_time, query.end_time)
+        .await?;
+    Ok(Json(RpcResponse::success(stats)))
+}
+
+async fn get_timeseries(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Query(query): Query<TimeseriesQuery>,
+) -> ApiResult<Vec<TimeBucket>> {
+    let buckets = scanner
+        .read()
         .await
-    {
-        Ok(transactions) => Json(RpcResponse::success(transactions)),
-        Err(e) => {
-            error!("Failed to get transactions: {}", e);
-            Json(RpcResponse::<Vec<Transaction>>::error(e.to_string()))
+        .get_timeseries(
+            query.address,
+            query.start_time,
+            query.end_time,
+            query.interval.unwrap_or_default(),
+        )
+        .await?;
+    Ok(Json(RpcResponse::success(buckets)))
+}
+
+async fn start_backfill(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Json(request): Json<BackfillRequest>,
+) -> ApiResult<String> {
+    if request.from > request.to {
+        return Err(ScannerError::InvalidRequest(
+            "from must not be greater than to".to_string(),
+        ));
+    }
+    if scanner.read().await.is_backfill_running() {
+        return Err(ScannerError::Conflict(
+            "A backfill is already running".to_string(),
+        ));
+    }
+
+    // 回填耗时较长，放到后台执行，进度通过 WebSocket 推送
+    tokio::spawn(async move {
+        if let Err(e) = scanner
+            .read()
+            .await
+            .backfill(request.from, request.to)
+            .await
+        {
+            error!("Backfill failed: {}", e);
         }
+    });
+
+    Ok(Json(RpcResponse::success("Backfill started".to_string())))
+}
+
+async fn cancel_backfill(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+) -> ApiResult<String> {
+    if scanner.read().await.cancel_backfill() {
+        Ok(Json(RpcResponse::success(
+            "Backfill cancelling".to_string(),
+        )))
+    } else {
+        Err(ScannerError::Conflict("No backfill is running".to_string()))
     }
 }
 
-async fn get_addresses(State(scanner): State<Arc<RwLock<BlockchainScanner>>>) -> impl IntoResponse {
-    let addresses = scanner.read().await.get_watched_addresses().await;
-    Json(RpcResponse::success(AddressResponse { addresses }))
+async fn set_checkpoint(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Json(request): Json<CheckpointRequest>,
+) -> ApiResult<CheckpointResponse> {
+    let previous = scanner.read().await.set_checkpoint(request.slot).await?;
+    Ok(Json(RpcResponse::success(CheckpointResponse {
+        previous,
+        slot: request.slot,
+    })))
+}
+
+async fn replay_dead_letters(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Query(query): Query<KafkaReplayQuery>,
+) -> ApiResult<KafkaReplayResult> {
+    let result = scanner
+        .read()
+        .await
+        .replay_dead_letters(query.effective_limit())
+        .await?;
+    Ok(Json(RpcResponse::success(result)))
+}
+
+async fn get_addresses(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    Query(query): Query<AddressQuery>,
+) -> ApiResult<AddressList> {
+    let addresses = scanner
+        .read()
+        .await
+        .get_watched_addresses_page(query)
+        .await?;
+    Ok(Json(RpcResponse::success(addresses)))
 }
 
 async fn add_address(
     State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
     Json(request): Json<AddAddressRequest>,
-) -> impl IntoResponse {
-    match scanner
+) -> ApiResult<String> {
+    scanner
         .read()
         .await
-        .add_watched_address(request.address.clone())
-        .await
-    {
-        Ok(_) => Json(RpcResponse::success(
-            "Address added successfully".to_string(),
-        )),
-        Err(e) => {
-            error!("Failed to add address: {}", e);
-            Json(RpcResponse::<String>::error(e.to_string()))
-        }
-    }
+        .add_watched_address(request.address, request.label)
+        .await?;
+    Ok(Json(RpcResponse::success(
+        "Address added successfully".to_string(),
+    )))
+}
+
+async fn reload_addresses(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+) -> ApiResult<AddressReloadResult> {
+    let result = scanner.read().await.load_watched_addresses().await?;
+    Ok(Json(RpcResponse::success(result)))
 }
 
 async fn remove_address(
     State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
     axum::extract::Path(address): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    match scanner
+) -> ApiResult<String> {
+    let removed = scanner
         .read()
         .await
         .remove_watched_address(address.clone())
-        .await
-    {
-        Ok(_) => Json(RpcResponse::success(
-            "Address removed successfully".to_string(),
-        )),
-        Err(e) => {
-            error!("Failed to remove address: {}", e);
-            Json(RpcResponse::<String>::error(e.to_string()))
-        }
+        .await?;
+    if !removed {
+        return Err(ScannerError::AddressNotFound(address));
     }
+    Ok(Json(RpcResponse::success(
+        "Address removed successfully".to_string(),
+    )))
+}
+
+async fn get_address_stats(
+    State(scanner): State<Arc<RwLock<BlockchainScanner>>>,
+    axum::extract::Path(address): axum::extract::Path<String>,
+) -> ApiResult<AddressStats> {
+    let stats = scanner.read().await.get_address_stats(address).await?;
+    Ok(Json(RpcResponse::success(stats)))
 }
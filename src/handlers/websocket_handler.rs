@@ -1,57 +1,135 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        State, WebSocketUpgrade,
+        Query, State, WebSocketUpgrade,
     },
-    response::Response,
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
     routing::get,
     Router,
 };
 use futures::{SinkExt, StreamExt};
 use serde_json;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 use uuid::Uuid;
 
+use crate::handlers::{self, rpc_handler, AppState, TlsAcceptor};
+use crate::models::TransactionType;
 use crate::services::websocket::WebSocketManager;
 
 #[derive(serde::Deserialize)]
 struct WebSocketMessage {
     action: String,
     address: Option<String>,
+    /// subscribe_mint / unsubscribe_mint 使用的代币 mint
+    mint: Option<String>,
+    /// 交易类型过滤，缺省表示全部类型
+    #[serde(default)]
+    types: Vec<TransactionType>,
+    /// 订阅时推送的历史交易条数
+    limit: Option<u32>,
 }
 
-pub async fn start_websocket_server(ws_manager: Arc<RwLock<WebSocketManager>>) {
-    let app = Router::new()
-        .route("/ws", get(websocket_handler))
-        .with_state(ws_manager);
+#[derive(serde::Deserialize)]
+struct AuthQuery {
+    token: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct WsState {
+    ws_manager: Arc<RwLock<WebSocketManager>>,
+    /// 未配置时不做鉴权
+    auth_token: Option<String>,
+}
+
+impl WsState {
+    pub fn new(ws_manager: Arc<RwLock<WebSocketManager>>, auth_token: Option<String>) -> Self {
+        Self {
+            ws_manager,
+            auth_token,
+        }
+    }
+}
+
+/// WebSocket 路由，单端口模式下由 RPC 服务合并挂载
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new().route("/ws", get(websocket_handler))
+}
 
-    let addr: std::net::SocketAddr = "0.0.0.0:8081".parse().unwrap();
-    info!("WebSocket server listening on {}", addr);
+pub async fn start_websocket_server(
+    state: AppState,
+    addr: SocketAddr,
+    tls: Option<TlsAcceptor>,
+    shutdown: CancellationToken,
+) -> std::io::Result<()> {
+    let app = routes()
+        .layer(middleware::from_fn(rpc_handler::propagate_request_id))
+        .with_state(state);
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let scheme = if tls.is_some() { "wss" } else { "ws" };
+    info!("WebSocket server listening on {}://{}", scheme, addr);
+
+    handlers::serve(app, addr, tls, shutdown).await
 }
 
 async fn websocket_handler(
+    State(state): State<WsState>,
+    Query(auth): Query<AuthQuery>,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
-    State(ws_manager): State<Arc<RwLock<WebSocketManager>>>,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, ws_manager))
+    if let Some(expected) = &state.auth_token {
+        // 支持 Authorization: Bearer <token> 或 ?token=<token>
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or(auth.token.as_deref());
+        if provided != Some(expected.as_str()) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+
+    // 连接数已达上限时拒绝升级
+    if state.ws_manager.read().await.is_full().await {
+        warn!("Rejecting WebSocket upgrade: connection limit reached");
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    let ws_manager = state.ws_manager;
+    ws.on_upgrade(move |socket| {
+        // 连接期间的日志都带上 connection_id，便于与客户端收到的 welcome 消息对应
+        let connection_id = Uuid::new_v4().to_string();
+        let span = info_span!("ws_connection", connection_id = %connection_id);
+        handle_socket(socket, ws_manager, connection_id).instrument(span)
+    })
 }
 
-async fn handle_socket(socket: WebSocket, ws_manager: Arc<RwLock<WebSocketManager>>) {
-    let connection_id = Uuid::new_v4().to_string();
+async fn handle_socket(
+    socket: WebSocket,
+    ws_manager: Arc<RwLock<WebSocketManager>>,
+    connection_id: String,
+) {
     let (sender, mut receiver) = socket.split();
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Message>();
+    let capacity = ws_manager.read().await.channel_capacity();
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Message>(capacity);
+    let cancel = CancellationToken::new();
 
-    // 添加连接到管理器
-    ws_manager
+    // 添加连接到管理器，并发升级时仍可能超过上限
+    if let Err(e) = ws_manager
         .write()
         .await
-        .add_connection(connection_id.clone(), tx.clone())
-        .await;
+        .add_connection(connection_id.clone(), tx.clone(), cancel.clone())
+        .await
+    {
+        warn!("Rejecting WebSocket connection {}: {}", connection_id, e);
+        return;
+    }
 
     info!("WebSocket connection established: {}", connection_id);
 
@@ -62,7 +140,7 @@ async fn handle_socket(socket: WebSocket, ws_manager: Arc<RwLock<WebSocketManage
         "message": "Connected to Solana scanner WebSocket"
     });
 
-    if tx.send(Message::Text(welcome_msg.to_string())).is_err() {
+    if tx.try_send(Message::Text(welcome_msg.to_string())).is_err() {
         error!("Failed to send welcome message to {}", connection_id);
         ws_manager
             .write()
@@ -72,18 +150,35 @@ async fn handle_socket(socket: WebSocket, ws_manager: Arc<RwLock<WebSocketManage
         return;
     }
 
-    // 从管理器转发消息到 websocket
+    // 从管理器转发消息到 websocket，连接被断开时立即停止
+    let forward_cancel = cancel.clone();
     tokio::spawn(async move {
         let mut forward = sender;
-        while let Some(msg) = rx.recv().await {
-            if forward.send(msg).await.is_err() {
-                break;
+        let pump = async {
+            while let Some(msg) = rx.recv().await {
+                if forward.send(msg).await.is_err() {
+                    break;
+                }
             }
+        };
+        tokio::select! {
+            _ = pump => {}
+            _ = forward_cancel.cancelled() => {}
         }
     });
 
     // 处理接收到的消息
-    while let Some(msg) = receiver.next().await {
+    loop {
+        let msg = tokio::select! {
+            msg = receiver.next() => msg,
+            _ = cancel.cancelled() => {
+                info!("WebSocket connection dropped by server: {}", connection_id);
+                break;
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
         match msg {
             Ok(Message::Text(text)) => {
                 debug!("Received message from {}: {}", connection_id, text);
@@ -98,7 +193,7 @@ async fn handle_socket(socket: WebSocket, ws_manager: Arc<RwLock<WebSocketManage
                             "type": "error",
                             "message": "Invalid message format"
                         });
-                        let _ = tx.send(Message::Text(error_msg.to_string()));
+                        let _ = tx.try_send(Message::Text(error_msg.to_string()));
                     }
                 }
             }
@@ -134,10 +229,11 @@ async fn handle_websocket_message(
         "subscribe" => {
             if let Some(address) = &msg.address {
                 let addr = address.clone();
+                // 快照需要查询数据库，只持有读锁以免阻塞交易广播
                 if let Err(e) = ws_manager
-                    .write()
+                    .read()
                     .await
-                    .subscribe_to_address(&connection_id.to_string(), addr)
+                    .subscribe_to_address(connection_id, addr, msg.types.clone(), msg.limit)
                     .await
                 {
                     error!("Failed to subscribe to address: {}", e);
@@ -151,7 +247,7 @@ async fn handle_websocket_message(
                 if let Err(e) = ws_manager
                     .write()
                     .await
-                    .unsubscribe_from_address(&connection_id.to_string(), address)
+                    .unsubscribe_from_address(connection_id, address)
                     .await
                 {
                     error!("Failed to unsubscribe from address: {}", e);
@@ -160,6 +256,69 @@ async fn handle_websocket_message(
                 error!("Unsubscribe action requires address");
             }
         }
+        "subscribe_mint" => {
+            if let Some(mint) = &msg.mint {
+                if let Err(e) = ws_manager
+                    .read()
+                    .await
+                    .subscribe_mint(connection_id, mint.clone(), msg.types.clone())
+                    .await
+                {
+                    error!("Failed to subscribe to mint: {}", e);
+                }
+            } else {
+                error!("subscribe_mint action requires mint");
+            }
+        }
+        "unsubscribe_mint" => {
+            if let Some(mint) = &msg.mint {
+                if let Err(e) = ws_manager
+                    .read()
+                    .await
+                    .unsubscribe_mint(connection_id, mint)
+                    .await
+                {
+                    error!("Failed to unsubscribe from mint: {}", e);
+                }
+            } else {
+                error!("unsubscribe_mint action requires mint");
+            }
+        }
+        "subscribe_all" => {
+            if let Err(e) = ws_manager
+                .write()
+                .await
+                .subscribe_all(connection_id, msg.types.clone())
+                .await
+            {
+                error!("Failed to subscribe to all transactions: {}", e);
+            }
+        }
+        "unsubscribe_all" => {
+            if let Err(e) = ws_manager.read().await.unsubscribe_all(connection_id).await {
+                error!("Failed to unsubscribe from all transactions: {}", e);
+            }
+        }
+        "subscribe_status" => {
+            if let Err(e) = ws_manager
+                .read()
+                .await
+                .subscribe_status(connection_id)
+                .await
+            {
+                error!("Failed to subscribe to scan status: {}", e);
+            }
+        }
+        "unsubscribe_status" => {
+            if let Err(e) = ws_manager
+                .read()
+                .await
+                .unsubscribe_status(connection_id)
+                .await
+            {
+                error!("Failed to unsubscribe from scan status: {}", e);
+            }
+        }
         _ => {
             error!("Unknown WebSocket action: {}", msg.action);
         }
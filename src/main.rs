@@ -1,7 +1,9 @@
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
 use tokio::sync::RwLock;
-use tracing::{error, info};
-use tracing_subscriber;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
 
 mod config;
 mod db;
@@ -10,65 +12,209 @@ mod models;
 mod services;
 mod utils;
 
-use config::AppConfig;
-use handlers::{rpc_handler, websocket_handler};
+use config::{AppConfig, LogFormat, ScanMode};
+use handlers::websocket_handler::WsState;
+use handlers::{rpc_handler, websocket_handler, AppState};
+use models::TransactionQuery;
 use services::{blockchain::BlockchainScanner, websocket::WebSocketManager};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 加载配置，日志格式由配置决定
+    let config = AppConfig::load()?;
+
     // 初始化日志
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+    init_tracing(config.log_format);
 
     info!("Starting Solana blockchain scanner service...");
+    config.validate()?;
+    if config.dry_run {
+        warn!("Dry run enabled: transactions and checkpoints will not be persisted or published");
+    }
 
-    // 加载配置
-    let config = AppConfig::load()?;
+    // 初始化 Prometheus 指标
+    let metrics_handle = utils::metrics::install()?;
 
     // 初始化数据库连接
-    let db_client = db::init_mongodb(&config.mongodb_uri).await?;
+    let db_client = db::init_mongodb(&config).await?;
 
     // 创建WebSocket管理器
-    let ws_manager = Arc::new(RwLock::new(WebSocketManager::new()));
+    let ws_manager = Arc::new(RwLock::new(WebSocketManager::new(
+        db_client.clone(),
+        config.ws_max_connections,
+        config.ws_channel_capacity,
+    )));
 
     // 创建区块链扫描器
     let scanner = Arc::new(RwLock::new(
-        BlockchainScanner::new(
-            config.solana_rpc_url.clone(),
-            db_client.clone(),
-            config.kafka_config.clone(),
-            ws_manager.clone(),
-            config.max_concurrent_requests,
-        )
-        .await?,
+        BlockchainScanner::new(&config, db_client.clone(), ws_manager.clone()).await?,
     ));
 
+    // 重新处理模式：按当前解析和分类逻辑重写已存储的交易并重新投递到 Kafka，完成后退出
+    if std::env::args().any(|arg| arg == "--reprocess") {
+        let scanner = scanner.read().await;
+        scanner
+            .reprocess_transactions(&TransactionQuery::default())
+            .await?;
+        scanner.shutdown().await;
+        return Ok(());
+    }
+
+    // 证书在启动时加载，路径错误或格式不对时直接退出，不回退为明文
+    let tls = handlers::load_tls(&config)?;
+
+    // 退出信号，所有后台任务共享
+    let shutdown = CancellationToken::new();
+
     // 启动区块链扫描任务
     let scanner_clone = scanner.clone();
-    let scan_task = tokio::spawn(async move {
-        if let Err(e) = scanner_clone.read().await.start_scanning().await {
+    let scan_mode = config.scan_mode;
+    let scan_shutdown = shutdown.clone();
+    let mut scan_task = tokio::spawn(async move {
+        let scanner = scanner_clone.read().await;
+        let result = match scan_mode {
+            ScanMode::Poll => scanner.start_scanning(scan_shutdown).await,
+            ScanMode::Stream => scanner.start_streaming(scan_shutdown).await,
+        };
+        if let Err(e) = result {
             error!("Blockchain scanning error: {}", e);
         }
     });
 
+    // 启动关注地址定时同步任务
+    let scanner_clone = scanner.clone();
+    let reload_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        scanner_clone
+            .read()
+            .await
+            .start_address_reload(reload_shutdown)
+            .await;
+    });
+
+    // RPC 与 WebSocket 服务共享的路由状态
+    let app_state = AppState {
+        scanner: scanner.clone(),
+        ws: WsState::new(ws_manager.clone(), config.ws_auth_token.clone()),
+    };
+    let single_port = config.single_port;
+
     // 启动WebSocket服务
-    let ws_manager_clone = ws_manager.clone();
-    let ws_task = tokio::spawn(async move {
-        websocket_handler::start_websocket_server(ws_manager_clone).await;
+    let ws_state = app_state.clone();
+    let ws_addr = config.websocket_addr();
+    let ws_tls = tls.clone();
+    let ws_shutdown = shutdown.clone();
+    let mut ws_task = tokio::spawn(async move {
+        // 单端口模式下 /ws 由 RPC 服务提供，这里只等待退出信号
+        if single_port {
+            ws_shutdown.cancelled().await;
+            return Ok(());
+        }
+        let result = websocket_handler::start_websocket_server(
+            ws_state,
+            ws_addr,
+            ws_tls,
+            ws_shutdown.clone(),
+        )
+        .await;
+        // 端口占用或 TLS 握手配置错误时通知其余任务一起退出
+        if let Err(e) = &result {
+            error!("WebSocket server error: {}", e);
+            ws_shutdown.cancel();
+        }
+        result
     });
 
     // 启动RPC服务
-    let rpc_task = tokio::spawn(async move {
-        rpc_handler::start_rpc_server(scanner.clone()).await;
+    let rpc_shutdown = shutdown.clone();
+    let rpc_api_keys = config.rpc_api_key.clone().unwrap_or_default();
+    let rpc_addr = config.rpc_addr();
+    let mut rpc_task = tokio::spawn(async move {
+        let result = rpc_handler::start_rpc_server(
+            app_state,
+            rpc_api_keys,
+            metrics_handle,
+            rpc_addr,
+            tls,
+            single_port,
+            rpc_shutdown.clone(),
+        )
+        .await;
+        if let Err(e) = &result {
+            error!("RPC server error: {}", e);
+            rpc_shutdown.cancel();
+        }
+        result
     });
 
-    // 等待所有任务完成
+    // 任一任务退出或收到退出信号；服务启动失败的错误在优雅退出后返回
+    let mut server_result: std::io::Result<()> = Ok(());
     tokio::select! {
-        _ = scan_task => info!("Scanner task completed"),
-        _ = ws_task => info!("WebSocket task completed"),
-        _ = rpc_task => info!("RPC task completed"),
+        _ = &mut scan_task => info!("Scanner task completed"),
+        result = &mut ws_task => {
+            info!("WebSocket task completed");
+            server_result = result.map_err(std::io::Error::from).and_then(|r| r);
+        }
+        result = &mut rpc_task => {
+            info!("RPC task completed");
+            server_result = result.map_err(std::io::Error::from).and_then(|r| r);
+        }
+        _ = shutdown_signal() => info!("Shutdown signal received"),
     }
 
+    // 优雅退出：停止扫描 -> 落盘检查点并清空 Kafka 队列 -> 关闭 WebSocket 连接 -> 等待服务停止
+    shutdown.cancel();
+    if !scan_task.is_finished() {
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, scan_task).await;
+    }
+    scanner.read().await.shutdown().await;
+    ws_manager.read().await.close_all_connections().await;
+    for task in [ws_task, rpc_task] {
+        if !task.is_finished() {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await;
+        }
+    }
+
+    info!("Solana blockchain scanner service stopped");
+    server_result?;
     Ok(())
 }
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+fn init_tracing(format: LogFormat) {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env());
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
+}
@@ -1,14 +1,22 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::doc;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::config::{Commitment, ScanMode};
+
+// 持久化的时间字段在 MongoDB 中统一以 BSON Date 存储，而不是 chrono 默认的 RFC 3339 字符串，
+// 否则 MongoDB 的时间范围查询、排序和 TTL 索引都无法按日期生效；API 的 JSON 中仍为 RFC 3339 字符串
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletAddress {
     pub id: String,
     pub address: String,
     pub label: Option<String>,
+    #[serde(with = "bson_datetime")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "bson_datetime")]
     pub updated_at: DateTime<Utc>,
     pub is_active: bool,
 }
@@ -31,25 +39,58 @@ impl WalletAddress {
 pub struct Transaction {
     pub id: String,
     pub signature: String,
+    /// 记录的唯一键：签名加指令位置（"<签名>:<下标>" 或 "<签名>:<下标>.<CPI 下标>"），
+    /// 同一笔交易中的多条转账、账户事件各自一条记录；Unknown 记录直接使用签名
+    #[serde(default)]
+    pub record_key: String,
     pub block_number: u64,
     pub transaction_type: TransactionType,
     pub from_address: String,
     pub to_address: Option<String>,
+    /// 命中的关注地址：发送方在关注列表中时为发送方，否则为接收方；旧记录为 None
+    #[serde(default)]
+    pub watched_address: Option<String>,
+    /// 展示用金额，由 amount_raw 按 decimals 换算
     pub amount: f64,
+    /// 链上原始单位的金额（lamports 或代币最小单位），对账以此为准。
+    /// 以十进制字符串存储，BSON 整数最大只到 i64::MAX
+    #[serde(default, with = "u64_as_string")]
+    pub amount_raw: u64,
+    /// amount_raw 的精度：SOL 为 9，代币为 mint 精度，未知时为 0
+    #[serde(default)]
+    pub decimals: u8,
     pub token_mint: Option<String>,
     pub token_symbol: Option<String>,
+    pub token_decimals: Option<u8>,
     pub fee: f64,
+    /// 优先费（SOL），交易未包含 ComputeBudget 指令时为 None
+    #[serde(default)]
+    pub priority_fee: Option<f64>,
+    /// 实际消耗的 compute units，交易未包含 ComputeBudget 指令时为 None
+    #[serde(default)]
+    pub compute_units: Option<u64>,
+    /// SPL Memo 备注
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(with = "bson_datetime")]
     pub timestamp: DateTime<Utc>,
+    /// 区块没有 block_time 时使用扫描时间代替，此时为 true
+    #[serde(default)]
+    pub timestamp_estimated: bool,
     pub status: TransactionStatus,
     pub raw_data: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionType {
     Native,
     Token,
     Nft,
+    /// 账户创建 / 代币账户初始化 / 关闭，事件详情在 raw_data 中
+    Account,
+    /// 涉及关注地址但指令尚未支持解析
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,7 +101,30 @@ pub enum TransactionStatus {
     Pending,
 }
 
+impl TransactionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionType::Native => "native",
+            TransactionType::Token => "token",
+            TransactionType::Nft => "nft",
+            TransactionType::Account => "account",
+            TransactionType::Unknown => "unknown",
+        }
+    }
+}
+
+impl TransactionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Confirmed => "confirmed",
+            TransactionStatus::Failed => "failed",
+            TransactionStatus::Pending => "pending",
+        }
+    }
+}
+
 impl Transaction {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         signature: String,
         block_number: u64,
@@ -70,6 +134,7 @@ impl Transaction {
         amount: f64,
         token_mint: Option<String>,
         token_symbol: Option<String>,
+        token_decimals: Option<u8>,
         fee: f64,
         timestamp: DateTime<Utc>,
         status: TransactionStatus,
@@ -77,41 +142,301 @@ impl Transaction {
     ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
+            record_key: signature.clone(),
             signature,
             block_number,
             transaction_type,
             from_address,
             to_address,
+            watched_address: None,
             amount,
+            amount_raw: 0,
+            decimals: 0,
             token_mint,
             token_symbol,
+            token_decimals,
             fee,
+            priority_fee: None,
+            compute_units: None,
+            memo: None,
             timestamp,
+            timestamp_estimated: false,
             status,
             raw_data,
         }
     }
+
+    /// 按指令位置设置记录键
+    pub fn set_position(&mut self, position: &str) {
+        self.record_key = format!("{}:{}", self.signature, position);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanStatus {
     pub id: String,
     pub last_scanned_block: u64,
+    #[serde(with = "bson_datetime")]
     pub last_scan_time: DateTime<Utc>,
     pub total_transactions_scanned: u64,
     pub is_scanning: bool,
 }
 
 impl ScanStatus {
+    pub const DEFAULT_ID: &'static str = "scan_status";
+
     pub fn new(last_scanned_block: u64) -> Self {
         Self {
-            id: "scan_status".to_string(),
+            id: Self::DEFAULT_ID.to_string(),
             last_scanned_block,
             last_scan_time: Utc::now(),
             total_transactions_scanned: 0,
             is_scanning: false,
         }
     }
+
+    /// 分片部署时的状态文档 id，未分片时沿用 scan_status 以兼容已有检查点
+    pub fn shard_id(shard_index: u64, shard_count: u64) -> String {
+        if shard_count > 1 {
+            format!("{}_{}", Self::DEFAULT_ID, shard_index)
+        } else {
+            Self::DEFAULT_ID.to_string()
+        }
+    }
+
+    /// 在 previous 基础上推进检查点并累加匹配数，沿用 is_scanning。slot 可能乱序完成，
+    /// last_scanned_block 只增不减，避免较早的 slot 后完成时检查点回退
+    pub fn advance(previous: Option<&ScanStatus>, last_block: u64, matched_count: u64) -> Self {
+        let mut status = Self::new(
+            previous
+                .map(|s| s.last_scanned_block.max(last_block))
+                .unwrap_or(last_block),
+        );
+        status.total_transactions_scanned =
+            previous.map(|s| s.total_transactions_scanned).unwrap_or(0) + matched_count;
+        status.is_scanning = previous.is_some_and(|s| s.is_scanning);
+        status
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissedSlot {
+    pub slot: u64,
+    pub error: String,
+    pub attempts: u32,
+    #[serde(with = "bson_datetime")]
+    pub first_failed_at: DateTime<Utc>,
+    #[serde(with = "bson_datetime")]
+    pub last_failed_at: DateTime<Utc>,
+}
+
+/// 正向扫描的租约锁，同一时间只有 holder 所在实例扫描
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScannerLock {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub holder: String,
+    #[serde(with = "bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 开启选主时实例的角色，未开启时始终为 leader
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScannerRole {
+    Leader,
+    Standby,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHash {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub blockhash: String,
+    pub previous_blockhash: String,
+    #[serde(with = "bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl BlockHash {
+    pub fn new(slot: u64, parent_slot: u64, blockhash: String, previous_blockhash: String) -> Self {
+        Self {
+            slot,
+            parent_slot,
+            blockhash,
+            previous_blockhash,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorgEvent {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub expected_previous_blockhash: String,
+    pub actual_previous_blockhash: String,
+    pub rollback_from: u64,
+    pub rollback_to: u64,
+    pub removed_transactions: u64,
+    #[serde(with = "bson_datetime")]
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressAction {
+    Added,
+    Removed,
+}
+
+/// 关注地址变更事件，投递到 address_events_topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressEvent {
+    pub action: AddressAction,
+    pub address: String,
+    pub label: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AddressEvent {
+    pub fn new(action: AddressAction, address: String, label: Option<String>) -> Self {
+        Self {
+            action,
+            address,
+            label,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Kafka 投递失败的交易，留待后续重放
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KafkaDeadLetter {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<mongodb::bson::oid::ObjectId>,
+    pub transaction: Transaction,
+    pub topic: String,
+    pub error: String,
+    #[serde(with = "bson_datetime")]
+    pub failed_at: DateTime<Utc>,
+}
+
+/// 关注地址的累计收发统计，由扫描到的交易增量维护
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressStats {
+    pub address: String,
+    /// 累计转入的 SOL（仅统计 native 交易）
+    pub total_received: f64,
+    /// 累计转出的 SOL（仅统计 native 交易）
+    pub total_sent: f64,
+    /// 涉及该地址的交易数（所有类型）
+    pub tx_count: u64,
+    #[serde(with = "bson_datetime")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AddressStats {
+    pub fn empty(address: String) -> Self {
+        Self {
+            address,
+            total_received: 0.0,
+            total_sent: 0.0,
+            tx_count: 0,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub from_slot: u64,
+    pub to_slot: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub total: u64,
+    pub finished: bool,
+    pub cancelled: bool,
+}
+
+/// Kafka 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// 冷却结束，正在放行请求试探 broker 是否恢复
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthDetail {
+    pub healthy: bool,
+    pub last_scanned_block: Option<u64>,
+    pub current_slot: Option<u64>,
+    pub lag: Option<u64>,
+    pub is_scanning: bool,
+    pub mongodb_connected: bool,
+    pub kafka_enabled: bool,
+    pub kafka_reachable: bool,
+    /// 未启用 Kafka 时为 None
+    pub kafka_circuit: Option<CircuitState>,
+    /// 启动后是否已追上链头
+    pub caught_up: bool,
+    pub role: ScannerRole,
+    /// 当前生效的粉尘过滤阈值，0 表示不过滤
+    pub min_lamports: u64,
+    pub min_token_amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramCapability {
+    pub name: String,
+    pub program_id: String,
+    /// 从该程序的指令中解析出的内容：transfer / memo / priority_fee
+    pub decodes: String,
+}
+
+impl ProgramCapability {
+    pub fn new(name: &str, program_id: &str, decodes: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            program_id: program_id.to_string(),
+            decodes: decodes.to_string(),
+        }
+    }
+}
+
+/// 扫描器能解析的程序与产出的交易类型，以及当前的扫描配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub programs: Vec<ProgramCapability>,
+    pub transaction_types: Vec<TransactionType>,
+    pub commitment: Commitment,
+    pub scan_mode: ScanMode,
+}
+
+/// 运行概况，供运维面板使用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    pub watched_addresses: usize,
+    pub ws_connections: usize,
+    pub ws_subscribed_addresses: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddressReloadResult {
+    pub added: usize,
+    pub removed: usize,
+    pub total: usize,
+}
+
+/// 重新处理已存储交易的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReprocessResult {
+    pub scanned: u64,
+    pub updated: u64,
+    pub failed: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,15 +467,209 @@ impl<T> RpcResponse<T> {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TransactionQuery {
+    /// 多个地址用逗号分隔
     pub address: Option<String>,
     pub transaction_type: Option<TransactionType>,
+    /// 匹配其中任一类型，供内部查询使用（如 WebSocket 快照按订阅类型过滤），不从请求参数读取；
+    /// 同时设置 transaction_type 时以 transaction_type 为准
+    #[serde(skip)]
+    pub transaction_types: Vec<TransactionType>,
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
+    /// 金额区间（含边界）
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// 游标分页使用，仅 /transactions/page 生效
+    pub cursor: Option<String>,
+    /// 排序方式，缺省按时间倒序；/transactions/page 固定按时间倒序
+    pub sort: Option<TransactionSort>,
+    /// 列表查询是否返回 raw_data，缺省不返回以减小响应体积
+    #[serde(default)]
+    pub include_raw: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSort {
+    TimestampAsc,
+    #[default]
+    TimestampDesc,
+    AmountDesc,
+}
+
+impl TransactionQuery {
+    /// address 支持逗号分隔的多个地址
+    pub fn addresses(&self) -> Vec<String> {
+        self.address
+            .iter()
+            .flat_map(|address| address.split(','))
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatsSummary {
+    pub total_transactions: u64,
+    pub by_type: HashMap<String, u64>,
+    pub by_status: HashMap<String, u64>,
+    pub total_native_volume: f64,
+    pub total_fees: f64,
+}
+
+/// 时间序列的分桶粒度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInterval {
+    #[default]
+    Day,
+    Hour,
+}
+
+impl TimeInterval {
+    /// 对应 $dateToString 的格式（UTC）
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            TimeInterval::Day => "%Y-%m-%d",
+            TimeInterval::Hour => "%Y-%m-%dT%H:00:00Z",
+        }
+    }
+}
+
+/// 按时间分桶的交易数量，volume 为主链币交易金额合计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeBucket {
+    pub date: String,
+    pub count: u64,
+    pub volume: f64,
+}
+
+/// GET /addresses 的分页与搜索参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddressQuery {
+    /// 匹配地址前缀或标签包含该字符串（不区分大小写）
+    pub search: Option<String>,
+    /// 标签包含该字符串（不区分大小写）
+    pub label: Option<String>,
     pub limit: Option<u32>,
     pub offset: Option<u32>,
 }
 
+impl AddressQuery {
+    pub const DEFAULT_LIMIT: u32 = 100;
+    pub const MAX_LIMIT: u32 = 1000;
+
+    /// 实际使用的 limit，缺省 100，最大 1000
+    pub fn effective_limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .clamp(1, Self::MAX_LIMIT)
+    }
+}
+
+/// POST /admin/kafka/replay 的参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KafkaReplayQuery {
+    pub limit: Option<u32>,
+}
+
+impl KafkaReplayQuery {
+    pub const DEFAULT_LIMIT: u32 = 100;
+    pub const MAX_LIMIT: u32 = 1000;
+
+    /// 单批重放的条数，缺省 100，最大 1000
+    pub fn effective_limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .clamp(1, Self::MAX_LIMIT)
+    }
+}
+
+/// 死信重放结果，remaining 为重放后集合中剩余的条数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KafkaReplayResult {
+    pub replayed: u64,
+    pub remaining: u64,
+}
+
+/// 关注地址分页结果，total 为满足搜索条件的有效地址总数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressList {
+    pub addresses: Vec<WalletAddress>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// offset 分页结果，total 为满足过滤条件的总数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionList {
+    pub data: Vec<Transaction>,
+    pub total: u64,
+    pub limit: Option<u32>,
+    pub offset: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionPage {
+    pub data: Vec<Transaction>,
+    pub next_cursor: Option<String>,
+}
+
+/// 时间字段：写入 MongoDB（非 human-readable 格式）时为 BSON Date，JSON 等 human-readable 格式为 RFC 3339 字符串
+mod bson_datetime {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            value.serialize(serializer)
+        } else {
+            mongodb::bson::DateTime::from_chrono(*value).serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        if deserializer.is_human_readable() {
+            DateTime::<Utc>::deserialize(deserializer)
+        } else {
+            mongodb::bson::DateTime::deserialize(deserializer).map(|dt| dt.to_chrono())
+        }
+    }
+}
+
+/// u64 以十进制字符串序列化；反序列化同时接受字符串和整数（旧记录以整数存储）
+mod u64_as_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match StringOrNumber::deserialize(deserializer)? {
+            StringOrNumber::String(s) => s.parse().map_err(serde::de::Error::custom),
+            StringOrNumber::Number(n) => Ok(n),
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
@@ -1,44 +1,174 @@
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::models::{Transaction, TransactionStatus, TransactionType, WalletAddress};
-    use chrono::Utc;
-
-    #[test]
-    fn test_wallet_address_creation() {
-        let address = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
-        let wallet = WalletAddress::new(address.to_string(), Some("test_wallet".to_string()));
-
-        assert_eq!(wallet.address, address);
-        assert_eq!(wallet.label, Some("test_wallet".to_string()));
-        assert!(wallet.is_active);
-    }
-
-    #[test]
-    fn test_transaction_creation() {
-        let signature = "5w6TpwP8pPhQ2EeFF3N7PQHQbmVjFduJR5WcKjdqSPM";
-        let from_address = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
-        let to_address = "8yKZtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
-
-        let transaction = Transaction::new(
-            signature.to_string(),
-            12345678,
-            TransactionType::Native,
-            from_address.to_string(),
-            Some(to_address.to_string()),
-            1.5,
-            None,
-            None,
-            0.00025,
-            Utc::now(),
-            TransactionStatus::Confirmed,
-            None,
-        );
-
-        assert_eq!(transaction.signature, signature);
-        assert_eq!(transaction.from_address, from_address);
-        assert_eq!(transaction.to_address, Some(to_address.to_string()));
-        assert_eq!(transaction.amount, 1.5);
-        assert_eq!(transaction.fee, 0.00025);
-    }
+use crate::models::{
+    AddressQuery, ScanStatus, Transaction, TransactionQuery, TransactionStatus, TransactionType,
+    WalletAddress,
+};
+use chrono::Utc;
+
+/// 测试用的 SOL 转账记录
+pub(crate) fn sample_transaction() -> Transaction {
+    Transaction::new(
+        "5w6TpwP8pPhQ2EeFF3N7PQHQbmVjFduJR5WcKjdqSPM".to_string(),
+        12345678,
+        TransactionType::Native,
+        "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU".to_string(),
+        None,
+        1.5,
+        None,
+        None,
+        None,
+        0.00025,
+        Utc::now(),
+        TransactionStatus::Confirmed,
+        None,
+    )
+}
+
+#[test]
+fn test_wallet_address_creation() {
+    let address = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+    let wallet = WalletAddress::new(address.to_string(), Some("test_wallet".to_string()));
+
+    assert_eq!(wallet.address, address);
+    assert_eq!(wallet.label, Some("test_wallet".to_string()));
+    assert!(wallet.is_active);
+}
+
+#[test]
+fn test_transaction_creation() {
+    let signature = "5w6TpwP8pPhQ2EeFF3N7PQHQbmVjFduJR5WcKjdqSPM";
+    let from_address = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+    let to_address = "8yKZtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+    let transaction = Transaction::new(
+        signature.to_string(),
+        12345678,
+        TransactionType::Native,
+        from_address.to_string(),
+        Some(to_address.to_string()),
+        1.5,
+        None,
+        None,
+        None,
+        0.00025,
+        Utc::now(),
+        TransactionStatus::Confirmed,
+        None,
+    );
+
+    assert_eq!(transaction.signature, signature);
+    assert_eq!(transaction.from_address, from_address);
+    assert_eq!(transaction.to_address, Some(to_address.to_string()));
+    assert_eq!(transaction.amount, 1.5);
+    assert_eq!(transaction.fee, 0.00025);
+}
+
+#[test]
+fn test_timestamp_serializes_as_bson_datetime() {
+    let transaction = sample_transaction();
+
+    // 驱动写入和读取 MongoDB 时使用非 human-readable 的 raw 序列化
+    let document = mongodb::bson::to_raw_document_buf(&transaction).unwrap();
+    assert_eq!(
+        document
+            .get_datetime("timestamp")
+            .unwrap()
+            .timestamp_millis(),
+        transaction.timestamp.timestamp_millis()
+    );
+
+    let decoded: Transaction = mongodb::bson::from_slice(document.as_bytes()).unwrap();
+    assert_eq!(
+        decoded.timestamp.timestamp_millis(),
+        transaction.timestamp.timestamp_millis()
+    );
+}
+
+#[test]
+fn test_timestamp_serializes_as_rfc3339_in_json() {
+    let transaction = sample_transaction();
+
+    let value = serde_json::to_value(&transaction).unwrap();
+    let timestamp = value["timestamp"].as_str().unwrap();
+    assert_eq!(
+        chrono::DateTime::parse_from_rfc3339(timestamp).unwrap(),
+        transaction.timestamp
+    );
+
+    let decoded: Transaction = serde_json::from_value(value).unwrap();
+    assert_eq!(decoded.timestamp, transaction.timestamp);
+}
+
+#[test]
+fn test_transaction_without_raw_data_deserializes() {
+    let mut transaction = sample_transaction();
+    transaction.raw_data = Some(serde_json::json!({ "type": "transfer" }));
+
+    // 列表查询的 projection 去掉了 raw_data
+    let mut document = mongodb::bson::to_document(&transaction).unwrap();
+    document.remove("raw_data");
+    let decoded: Transaction = mongodb::bson::from_document(document).unwrap();
+    assert!(decoded.raw_data.is_none());
+    assert_eq!(decoded.signature, transaction.signature);
+}
+
+#[test]
+fn test_amount_raw_above_i64_max_round_trips() {
+    let mut transaction = sample_transaction();
+    transaction.transaction_type = TransactionType::Token;
+    transaction.amount_raw = u64::MAX;
+
+    let mut document = mongodb::bson::to_document(&transaction).unwrap();
+    assert_eq!(
+        document.get_str("amount_raw").unwrap(),
+        u64::MAX.to_string()
+    );
+    let decoded: Transaction = mongodb::bson::from_document(document.clone()).unwrap();
+    assert_eq!(decoded.amount_raw, u64::MAX);
+
+    // 旧记录以整数存储
+    document.insert("amount_raw", 1_000_000_000i64);
+    let decoded: Transaction = mongodb::bson::from_document(document).unwrap();
+    assert_eq!(decoded.amount_raw, 1_000_000_000);
+}
+
+#[test]
+fn test_transaction_query_addresses() {
+    let query = TransactionQuery {
+        address: Some("addr1, addr2,,addr3 ".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(query.addresses(), vec!["addr1", "addr2", "addr3"]);
+    assert!(TransactionQuery::default().addresses().is_empty());
+}
+
+#[test]
+fn test_scan_status_advance_is_monotonic() {
+    // slot 10 先完成，随后较早的 slot 8 才完成：检查点不回退，匹配数照常累加
+    let first = ScanStatus::advance(None, 10, 2);
+    let second = ScanStatus::advance(Some(&first), 8, 3);
+    assert_eq!(second.last_scanned_block, 10);
+    assert_eq!(second.total_transactions_scanned, 5);
+
+    let third = ScanStatus::advance(Some(&second), 12, 0);
+    assert_eq!(third.last_scanned_block, 12);
+    assert_eq!(third.total_transactions_scanned, 5);
+}
+
+#[test]
+fn test_address_query_limit_is_clamped() {
+    assert_eq!(AddressQuery::default().effective_limit(), 100);
+    let query = AddressQuery {
+        limit: Some(50_000),
+        ..Default::default()
+    };
+    assert_eq!(query.effective_limit(), 1000);
+}
+
+#[test]
+fn test_scan_status_advance_keeps_is_scanning() {
+    let mut initial = ScanStatus::new(99);
+    initial.is_scanning = true;
+    let next = ScanStatus::advance(Some(&initial), 100, 1);
+    assert!(next.is_scanning);
+    assert!(!ScanStatus::advance(None, 100, 1).is_scanning);
 }
@@ -1,55 +1,231 @@
-use anyhow::Result;
-use chrono::Utc;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use futures::stream::{self, StreamExt};
+use futures::TryStreamExt;
+use lru::LruCache;
 use mongodb::Database;
-use solana_client::rpc_client::RpcClient;
+use solana_client::client_error::{ClientError, Result as ClientResult};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
-use solana_transaction_status::UiTransactionEncoding;
-use std::collections::HashSet;
+use solana_transaction_status::{
+    EncodedTransaction, UiConfirmedBlock, UiMessage, UiTransactionEncoding, UiTransactionStatusMeta,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::{interval, Duration};
-use futures::stream::{self, StreamExt};
-use tracing::{debug, error, info};
+use tokio::time::{interval, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, warn, Span};
 
-use crate::config::KafkaConfig;
-use crate::db::{ScanStatusRepo, TransactionRepo, WalletAddressRepo};
-use crate::models::{ScanStatus, Transaction, TransactionType};
+use crate::config::{AppConfig, Commitment, ScanMode};
+use crate::db::{
+    AddressStatsRepo, BlockHashRepo, DeadLetterRepo, MissedSlotsRepo, ScanStatusRepo,
+    ScannerLockRepo, TransactionRepo, WalletAddressRepo,
+};
+use crate::models::{
+    AddressAction, AddressEvent, AddressList, AddressQuery, AddressReloadResult, AddressStats,
+    BackfillProgress, BlockHash, Capabilities, CircuitState, HealthDetail, KafkaDeadLetter,
+    KafkaReplayResult, ReorgEvent, ReprocessResult, ScanStatus, ScannerRole, ServiceInfo,
+    StatsSummary, TimeBucket, TimeInterval, Transaction, TransactionList, TransactionPage,
+    TransactionQuery, TransactionStatus, TransactionType, WalletAddress,
+};
+use crate::services::parser;
+use crate::services::rpc_pool::{RpcPool, RpcRateLimiter};
+use crate::services::sink::{TransactionSink, WebSocketSink, WebhookSink};
+use crate::services::token_metadata::TokenMetadataService;
 use crate::services::websocket::WebSocketManager;
+use crate::utils::error::ScannerError;
 use crate::utils::kafka::KafkaProducer;
+use crate::utils::metrics;
+use crate::utils::retry::{backoff_delay, classify_rpc_error, RpcErrorClass};
+use crate::utils::validation::validate_address;
 
 pub struct BlockchainScanner {
-    rpc_client: RpcClient,
+    rpc_pool: Arc<RpcPool>,
+    commitment: CommitmentConfig,
+    /// 配置中的 commitment 与扫描模式，供 /capabilities 返回
+    configured_commitment: Commitment,
+    scan_mode: ScanMode,
     db: Database,
-    kafka_producer: Arc<KafkaProducer>,
+    /// 未启用 Kafka 时为 None，仍用于投递分叉事件、健康检查和退出时清空队列
+    kafka_producer: Option<Arc<KafkaProducer>>,
+    /// 交易输出，按顺序依次投递
+    sinks: Arc<Vec<Box<dyn TransactionSink>>>,
+    token_metadata: TokenMetadataService,
     watched_addresses: Arc<RwLock<HashSet<String>>>,
     scan_status: Arc<RwLock<Option<ScanStatus>>>,
-    ws_manager: Arc<RwLock<WebSocketManager>>, 
+    ws_manager: Arc<RwLock<WebSocketManager>>,
+    scan_interval: Duration,
     max_concurrent_requests: usize,
+    max_slots_per_tick: u64,
+    max_addresses: usize,
+    reorg_depth: u64,
+    initial_lookback_slots: u64,
+    start_slot: Option<u64>,
+    /// 分片部署：只扫描 slot % shard_count == shard_index 的 slot
+    shard_index: u64,
+    shard_count: u64,
+    scan_status_id: String,
+    leader_election: bool,
+    leader_lease_secs: u64,
+    instance_id: String,
+    /// 开启选主时是否持有租约
+    is_leader: AtomicBool,
+    max_retries: u32,
+    retry_base_delay_ms: u64,
+    missed_slot_retry_secs: u64,
+    address_reload_secs: u64,
+    health_max_slot_lag: u64,
+    catch_up_threshold_slots: u64,
+    /// 启动后首次追上链头时置为 true，之后不再回退
+    caught_up: AtomicBool,
+    program_allowlist: Option<HashSet<String>>,
+    program_denylist: HashSet<String>,
+    store_failed_transactions: bool,
+    min_lamports: u64,
+    min_token_amount: u64,
+    solana_ws_url: String,
+    backfill_running: AtomicBool,
+    backfill_cancelled: AtomicBool,
+    /// 最近一次获取到的链上最新 slot，用于计算扫描进度推送中的 lag
+    latest_slot: AtomicU64,
+    dry_run: bool,
+    /// 上次推送扫描进度的时间，用于限流
+    last_progress_push: std::sync::Mutex<Option<Instant>>,
+    /// 每次手动设置检查点时加一，之前开始的扫描轮次不再写入检查点，避免覆盖手动设置的值
+    checkpoint_epoch: AtomicU64,
+    /// 最近处理过的区块，分叉校验时优先读取，避免每个 slot 都查询数据库
+    block_cache: std::sync::Mutex<LruCache<u64, CachedBlock>>,
+}
+
+struct CachedBlock {
+    blockhash: String,
+    block_time: Option<i64>,
 }
 
+// 订阅模式下收到的是 processed 级别的 slot，落后几个 slot 再拉取 confirmed 区块
+const STREAM_CONFIRMATION_LAG: u64 = 2;
+
+// 回填进度推送间隔（slot 数）
+const BACKFILL_PROGRESS_INTERVAL: u64 = 50;
+
+// 重新处理时每处理多少条记录输出一次进度日志
+const REPROCESS_LOG_INTERVAL: u64 = 1000;
+
+// 扫描进度推送的最小间隔
+const SCAN_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 impl BlockchainScanner {
     pub async fn new(
-        rpc_url: String,
+        config: &AppConfig,
         db: Database,
-        kafka_config: KafkaConfig,
         ws_manager: Arc<RwLock<WebSocketManager>>,
-        max_concurrent_requests: usize,
     ) -> Result<Self> {
-        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
-        let kafka_producer = Arc::new(KafkaProducer::new(&kafka_config).await?);
+        if config.solana_rpc_urls.is_empty() {
+            return Err(anyhow!("At least one Solana RPC URL must be configured"));
+        }
+        let commitment = config.commitment.to_commitment_config();
+        let rate_limiter = RpcRateLimiter::new(config.rpc_requests_per_second);
+        // 请求超时后由 rpc_with_retry 按瞬时错误换端点重试，避免慢节点拖住扫描
+        let rpc_timeout = Duration::from_secs(config.rpc_timeout_secs);
+        let rpc_pool = Arc::new(RpcPool::new(
+            &config.solana_rpc_urls,
+            commitment,
+            rpc_timeout,
+            rate_limiter,
+        ));
+        let kafka_producer = if config.kafka_config.enabled {
+            Some(Arc::new(KafkaProducer::new(&config.kafka_config).await?))
+        } else {
+            info!("Kafka disabled, transactions will only be broadcast over WebSocket");
+            None
+        };
+        let mut sinks: Vec<Box<dyn TransactionSink>> = Vec::new();
+        if let Some(kafka) = &kafka_producer {
+            sinks.push(Box::new(kafka.clone()));
+        }
+        sinks.push(Box::new(WebSocketSink::new(ws_manager.clone())));
+        if let Some(url) = &config.webhook_url {
+            sinks.push(Box::new(WebhookSink::new(
+                url.clone(),
+                config.webhook_secret.clone(),
+                Duration::from_millis(config.webhook_timeout_ms),
+                config.webhook_max_retries,
+                config.retry_base_delay_ms,
+            )?));
+            info!("Webhook sink enabled");
+        }
+        let token_metadata =
+            TokenMetadataService::new(rpc_pool.clone(), config.token_metadata_ttl_secs);
 
         let scanner = Self {
-            rpc_client,
+            rpc_pool,
+            commitment,
+            configured_commitment: config.commitment,
+            scan_mode: config.scan_mode,
             db,
             kafka_producer,
+            sinks: Arc::new(sinks),
+            token_metadata,
             watched_addresses: Arc::new(RwLock::new(HashSet::new())),
             scan_status: Arc::new(RwLock::new(None)),
             ws_manager,
-            max_concurrent_requests,
+            scan_interval: config.scan_interval(),
+            max_concurrent_requests: config.max_concurrent_requests,
+            max_slots_per_tick: config.max_slots_per_tick,
+            max_addresses: config.max_addresses,
+            reorg_depth: config.reorg_depth,
+            initial_lookback_slots: config.initial_lookback_slots,
+            start_slot: config.start_slot,
+            shard_index: config.shard_index,
+            shard_count: config.shard_count,
+            scan_status_id: ScanStatus::shard_id(config.shard_index, config.shard_count),
+            leader_election: config.leader_election,
+            leader_lease_secs: config.leader_lease_secs,
+            instance_id: config.instance_id.clone().unwrap_or_else(|| {
+                let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "scanner".to_string());
+                format!(
+                    "{}-{}",
+                    host,
+                    &uuid::Uuid::new_v4().simple().to_string()[..8]
+                )
+            }),
+            is_leader: AtomicBool::new(false),
+            max_retries: config.max_retries,
+            retry_base_delay_ms: config.retry_base_delay_ms,
+            missed_slot_retry_secs: config.missed_slot_retry_secs,
+            address_reload_secs: config.address_reload_secs,
+            health_max_slot_lag: config.health_max_slot_lag,
+            catch_up_threshold_slots: config.catch_up_threshold_slots,
+            caught_up: AtomicBool::new(false),
+            checkpoint_epoch: AtomicU64::new(0),
+            program_allowlist: config
+                .program_allowlist
+                .as_ref()
+                .filter(|list| !list.is_empty())
+                .map(|list| list.iter().cloned().collect()),
+            program_denylist: config.program_denylist.iter().flatten().cloned().collect(),
+            store_failed_transactions: config.store_failed_transactions,
+            min_lamports: config.min_lamports,
+            min_token_amount: config.min_token_amount,
+            solana_ws_url: config.solana_ws_url.clone(),
+            backfill_running: AtomicBool::new(false),
+            backfill_cancelled: AtomicBool::new(false),
+            latest_slot: AtomicU64::new(0),
+            dry_run: config.dry_run,
+            last_progress_push: std::sync::Mutex::new(None),
+            block_cache: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(config.block_cache_size).unwrap_or(NonZeroUsize::MIN),
+            )),
         };
 
         // 加载关注的钱包地址
-        scanner.load_watched_addresses().await?;
+        let loaded = scanner.load_watched_addresses().await?;
+        info!("Loaded {} watched addresses", loaded.total);
 
         // 加载扫描状态
         scanner.load_scan_status().await?;
@@ -57,348 +233,1757 @@ impl BlockchainScanner {
         Ok(scanner)
     }
 
-    async fn load_watched_addresses(&self) -> Result<()> {
+    /// 以数据库中的有效地址为准重建内存中的关注集合，返回新增/移除数量
+    pub async fn load_watched_addresses(&self) -> Result<AddressReloadResult> {
         let repo = WalletAddressRepo::new(self.db.clone());
-        let addresses = repo.get_all_active_addresses().await?;
 
+        // 查询期间持有写锁，避免与 add/remove 交错导致刚写入的地址被误删
         let mut watched = self.watched_addresses.write().await;
-        for addr in addresses {
-            watched.insert(addr.address.clone());
+        let active: HashSet<String> = repo
+            .get_all_active_addresses()
+            .await?
+            .into_iter()
+            .map(|addr| addr.address)
+            .collect();
+
+        let result = AddressReloadResult {
+            added: active.difference(&watched).count(),
+            removed: watched.difference(&active).count(),
+            total: active.len(),
+        };
+        *watched = active;
+
+        if result.added > 0 || result.removed > 0 {
+            info!(
+                "Reloaded watched addresses: +{} -{} (total {})",
+                result.added, result.removed, result.total
+            );
         }
+        Ok(result)
+    }
 
-        info!("Loaded {} watched addresses", watched.len());
-        Ok(())
+    /// 定时从数据库同步关注地址，感知其他进程/实例的增删
+    pub async fn start_address_reload(&self, shutdown: CancellationToken) {
+        let mut reload_interval = interval(Duration::from_secs(std::cmp::max(
+            1,
+            self.address_reload_secs,
+        )));
+        // 首次 tick 立即触发，启动时已加载过，跳过
+        reload_interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = reload_interval.tick() => {
+                    if let Err(e) = self.load_watched_addresses().await {
+                        error!("Error reloading watched addresses: {}", e);
+                    }
+                }
+            }
+        }
     }
 
     async fn load_scan_status(&self) -> Result<()> {
-        let repo = ScanStatusRepo::new(self.db.clone());
+        let repo = ScanStatusRepo::new(self.db.clone(), &self.scan_status_id);
         let status = repo.get_scan_status().await?;
 
-        let mut scan_status = self.scan_status.write().await;
-        *scan_status = status;
+        if let (Some(status), Some(start_slot)) = (status.as_ref(), self.start_slot) {
+            warn!(
+                "Ignoring START_SLOT {}: resuming from checkpoint at block {}",
+                start_slot, status.last_scanned_block
+            );
+        }
+
+        let missing = status.is_none();
+        *self.scan_status.write().await = status;
+
+        // 首次启动时立即确定起点并落库；获取 slot 失败则留到第一轮扫描时再初始化
+        if missing {
+            match self
+                .rpc_with_retry("get_slot", |client| async move { client.get_slot().await })
+                .await
+            {
+                Ok(current_slot) => {
+                    self.init_scan_status(current_slot).await;
+                }
+                Err(e) => warn!(
+                    "Failed to get current slot, scan checkpoint will be initialized on first scan: {}",
+                    e
+                ),
+            }
+        }
 
         Ok(())
     }
 
-    pub async fn start_scanning(&self) -> Result<()> {
+    /// 没有检查点时按 start_slot 或 initial_lookback_slots 确定起点，并立即写入检查点
+    /// （起点的前一个 slot），之后即使第一轮扫描失败，起点也不会随链头漂移。返回起始 slot
+    async fn init_scan_status(&self, current_slot: u64) -> u64 {
+        let mut scan_status = self.scan_status.write().await;
+        if let Some(status) = scan_status.as_ref() {
+            return status.last_scanned_block + 1;
+        }
+
+        let start_slot = self
+            .start_slot
+            .unwrap_or_else(|| current_slot.saturating_sub(self.initial_lookback_slots));
+        let mut status = ScanStatus::new(start_slot.saturating_sub(1));
+        status.id = self.scan_status_id.clone();
+        status.is_scanning = true;
+        if !self.dry_run {
+            let repo = ScanStatusRepo::new(self.db.clone(), &self.scan_status_id);
+            if let Err(e) = repo.update_scan_status(&status).await {
+                warn!("Failed to persist initial scan status: {}", e);
+            }
+        }
+        info!(
+            "Initialized scan checkpoint, scanning from block {}",
+            start_slot
+        );
+        *scan_status = Some(status);
+
+        start_slot
+    }
+
+    /// 轮询模式：定时获取最新 slot 并扫描，shutdown 被取消时退出
+    pub async fn start_scanning(&self, shutdown: CancellationToken) -> Result<()> {
+        loop {
+            if !self.acquire_lease(&shutdown).await {
+                return Ok(());
+            }
+            self.set_scanning(true).await;
+            let result = tokio::select! {
+                _ = shutdown.cancelled() => {
+                    info!("Blockchain scanning stopped");
+                    Ok(())
+                }
+                result = self.poll_loop() => result,
+                _ = self.hold_lease() => {
+                    self.enter_standby().await;
+                    continue;
+                }
+            };
+            self.finish_scanning(&result).await;
+            return result;
+        }
+    }
+
+    async fn poll_loop(&self) -> Result<()> {
         info!("Starting blockchain scanning...");
 
-        let mut scan_interval = interval(Duration::from_millis(200));
+        let mut scan_interval = interval(self.scan_interval);
+        let mut missed_slot_interval = interval(Duration::from_secs(std::cmp::max(
+            1,
+            self.missed_slot_retry_secs,
+        )));
+
+        loop {
+            tokio::select! {
+                _ = scan_interval.tick() => {
+                    if let Err(e) = self.scan_blocks().await {
+                        error!("Error scanning blocks: {}", e);
+                    }
+                }
+                _ = missed_slot_interval.tick() => {
+                    if let Err(e) = self.retry_missed_slots().await {
+                        error!("Error retrying missed slots: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 订阅模式：通过 PubSub slotSubscribe 接收新 slot 后扫描，
+    /// 连接或订阅失败（服务商不支持）时退回轮询模式
+    pub async fn start_streaming(&self, shutdown: CancellationToken) -> Result<()> {
+        loop {
+            if !self.acquire_lease(&shutdown).await {
+                return Ok(());
+            }
+            self.set_scanning(true).await;
+            let result = tokio::select! {
+                _ = shutdown.cancelled() => {
+                    info!("Blockchain streaming stopped");
+                    Ok(())
+                }
+                result = self.stream_loop() => result,
+                _ = self.hold_lease() => {
+                    self.enter_standby().await;
+                    continue;
+                }
+            };
+            self.finish_scanning(&result).await;
+            return result;
+        }
+    }
+
+    /// 扫描循环退出后将 is_scanning 置为 false 并释放租约，异常退出时额外记录错误，便于告警
+    async fn finish_scanning(&self, result: &Result<()>) {
+        if let Err(e) = result {
+            error!("Scan loop exited unexpectedly: {}", e);
+        }
+        self.set_scanning(false).await;
+
+        if self.leader_election && self.is_leader.swap(false, Ordering::SeqCst) {
+            let repo = ScannerLockRepo::new(self.db.clone());
+            if let Err(e) = repo.release(&self.scan_status_id, &self.instance_id).await {
+                warn!("Failed to release scanner lease: {}", e);
+            }
+        }
+    }
 
+    /// 未开启选主时直接返回 true；否则在待命状态下定期尝试获取租约，成功后重新加载
+    /// 检查点（待命期间由其他实例推进）。shutdown 被取消时返回 false
+    async fn acquire_lease(&self, shutdown: &CancellationToken) -> bool {
+        if !self.leader_election {
+            return true;
+        }
+
+        let mut ticker = interval(self.lease_renew_interval());
+        let mut announced = false;
         loop {
-            scan_interval.tick().await;
+            tokio::select! {
+                _ = shutdown.cancelled() => return false,
+                _ = ticker.tick() => {}
+            }
+            match self.try_acquire_lease().await {
+                Ok(true) => break,
+                Ok(false) if !announced => {
+                    info!("Scanner lease is held by another instance, waiting in standby");
+                    announced = true;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Failed to acquire scanner lease: {}", e),
+            }
+        }
 
-            if let Err(e) = self.scan_blocks().await {
-                error!("Error scanning blocks: {}", e);
+        info!("Acquired scanner lease as {}", self.instance_id);
+        self.is_leader.store(true, Ordering::SeqCst);
+        if let Err(e) = self.load_scan_status().await {
+            warn!("Failed to reload scan status after taking over: {}", e);
+        }
+        true
+    }
+
+    /// 定期续期租约，租约被其他实例接管时返回；未开启选主时永不返回
+    async fn hold_lease(&self) {
+        if !self.leader_election {
+            return std::future::pending().await;
+        }
+
+        let mut ticker = interval(self.lease_renew_interval());
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match self.try_acquire_lease().await {
+                Ok(true) => {}
+                Ok(false) => {
+                    warn!("Scanner lease was taken over by another instance");
+                    return;
+                }
+                Err(e) => warn!("Failed to renew scanner lease: {}", e),
             }
         }
     }
 
-    async fn scan_blocks(&self) -> Result<()> {
-        let current_slot = self.rpc_client.get_slot()?;
-        let start_slot = {
-            let scan_status = self.scan_status.read().await;
-            if let Some(status) = scan_status.as_ref() {
-                status.last_scanned_block + 1
-            } else {
-                current_slot.saturating_sub(300)
+    /// 失去租约后回到待命状态；is_scanning 只改内存，数据库中的状态由新的 leader 维护
+    async fn enter_standby(&self) {
+        self.is_leader.store(false, Ordering::SeqCst);
+        if let Some(status) = self.scan_status.write().await.as_mut() {
+            status.is_scanning = false;
+        }
+        info!("Scanner is now in standby");
+    }
+
+    async fn try_acquire_lease(&self) -> Result<bool> {
+        let repo = ScannerLockRepo::new(self.db.clone());
+        repo.try_acquire(
+            &self.scan_status_id,
+            &self.instance_id,
+            chrono::Duration::seconds(self.leader_lease_secs as i64),
+        )
+        .await
+    }
+
+    fn lease_renew_interval(&self) -> Duration {
+        Duration::from_secs(std::cmp::max(1, self.leader_lease_secs / 3))
+    }
+
+    pub fn role(&self) -> ScannerRole {
+        if !self.leader_election || self.is_leader.load(Ordering::SeqCst) {
+            ScannerRole::Leader
+        } else {
+            ScannerRole::Standby
+        }
+    }
+
+    /// 更新并落库 is_scanning，尚无检查点时只在初始化检查点时写入
+    async fn set_scanning(&self, scanning: bool) {
+        let mut scan_status = self.scan_status.write().await;
+        let Some(status) = scan_status.as_mut() else {
+            return;
+        };
+        status.is_scanning = scanning;
+        if self.dry_run {
+            return;
+        }
+        let repo = ScanStatusRepo::new(self.db.clone(), &self.scan_status_id);
+        if let Err(e) = repo.update_scan_status(status).await {
+            warn!("Failed to persist is_scanning={}: {}", scanning, e);
+        }
+    }
+
+    async fn stream_loop(&self) -> Result<()> {
+        info!(
+            "Starting blockchain streaming via {}...",
+            self.solana_ws_url
+        );
+
+        let mut missed_slot_interval = interval(Duration::from_secs(std::cmp::max(
+            1,
+            self.missed_slot_retry_secs,
+        )));
+
+        loop {
+            let pubsub = match PubsubClient::new(&self.solana_ws_url).await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("PubSub connection failed, falling back to polling: {}", e);
+                    return self.poll_loop().await;
+                }
+            };
+            let (mut slots, unsubscribe) = match pubsub.slot_subscribe().await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    warn!(
+                        "slotSubscribe not supported, falling back to polling: {}",
+                        e
+                    );
+                    return self.poll_loop().await;
+                }
+            };
+
+            loop {
+                tokio::select! {
+                    slot_info = slots.next() => {
+                        let Some(slot_info) = slot_info else {
+                            break;
+                        };
+                        let target = slot_info.slot.saturating_sub(STREAM_CONFIRMATION_LAG);
+                        if let Err(e) = self.scan_up_to(target).await {
+                            error!("Error scanning blocks: {}", e);
+                        }
+                    }
+                    _ = missed_slot_interval.tick() => {
+                        if let Err(e) = self.retry_missed_slots().await {
+                            error!("Error retrying missed slots: {}", e);
+                        }
+                    }
+                }
             }
+
+            unsubscribe().await;
+            warn!("Slot subscription ended, reconnecting...");
+        }
+    }
+
+    async fn scan_blocks(&self) -> Result<()> {
+        let current_slot = self
+            .rpc_with_retry("get_slot", |client| async move { client.get_slot().await })
+            .await?;
+        self.scan_up_to(current_slot).await
+    }
+
+    /// 从检查点扫描到 current_slot
+    async fn scan_up_to(&self, current_slot: u64) -> Result<()> {
+        self.latest_slot.fetch_max(current_slot, Ordering::Relaxed);
+        let epoch = self.checkpoint_epoch.load(Ordering::SeqCst);
+        // 从检查点的下一个 slot 开始，没有检查点时先初始化
+        let checkpoint = self
+            .scan_status
+            .read()
+            .await
+            .as_ref()
+            .map(|status| status.last_scanned_block);
+        let start_slot = match checkpoint {
+            Some(checkpoint) => checkpoint + 1,
+            None => self.init_scan_status(current_slot).await,
         };
 
         if start_slot > current_slot {
             debug!("No new blocks to scan");
+            metrics::set_slot_lag(0);
+            self.check_caught_up(0);
             return Ok(());
         }
 
-        info!("Scanning blocks from {} to {}", start_slot, current_slot);
+        // 每轮最多扫描 max_slots_per_tick 个 slot，剩余部分留给下一轮
+        let end_slot = current_slot
+            .min(start_slot.saturating_add(std::cmp::max(1, self.max_slots_per_tick) - 1));
+        if end_slot < current_slot {
+            info!(
+                "Scanning blocks from {} to {} ({} slots behind, catching up)",
+                start_slot,
+                end_slot,
+                current_slot - start_slot + 1
+            );
+        } else {
+            info!("Scanning blocks from {} to {}", start_slot, end_slot);
+        }
+
+        // 分片部署时只扫描本实例负责的 slot，其他分片的 slot 视为已完成，检查点照常连续推进
+        let mut completed: BTreeMap<u64, u64> = (start_slot..=end_slot)
+            .filter(|slot| !self.owns_slot(*slot))
+            .map(|slot| (slot, 0))
+            .collect();
+        let owned_slots = (start_slot..=end_slot).filter(|slot| self.owns_slot(*slot));
 
         let concurrency = std::cmp::max(1, self.max_concurrent_requests);
-        stream::iter(start_slot..=current_slot)
+        let mut results = stream::iter(owned_slots)
             .map(|slot| async move { (slot, self.scan_block(slot).await) })
-            .buffer_unordered(concurrency)
-            .for_each(|res| async move {
-                let (slot, outcome) = res;
-                match outcome {
-                    Ok(_) => { let _ = self.update_scan_status(slot).await; }
-                    Err(e) => { error!("Error scanning block {}: {}", slot, e); }
+            .buffer_unordered(concurrency);
+
+        // 结果按完成顺序返回，检查点只推进到连续完成的最高 slot
+        let mut next_checkpoint = start_slot;
+        while let Some((slot, outcome)) = results.next().await {
+            let matched = match outcome {
+                Ok(count) => Some(count),
+                Err(e) if is_slot_skipped(&e) => {
+                    debug!("Slot {} was skipped, nothing to scan", slot);
+                    Some(0)
                 }
-            })
-            .await;
+                Err(e) => {
+                    error!("Error scanning block {}: {}", slot, e);
+                    // 失败的 slot 不算完成，检查点停在它之前，下一轮从这里重新扫描；
+                    // 同时写入 missed_slots，由重试任务在两轮之间补扫
+                    self.record_missed_slot(slot, &e.to_string()).await;
+                    None
+                }
+            };
+            let Some(matched) = matched else {
+                continue;
+            };
+
+            completed.insert(slot, matched);
+            let mut advanced_matched = 0;
+            let previous_checkpoint = next_checkpoint;
+            while let Some(count) = completed.remove(&next_checkpoint) {
+                advanced_matched += count;
+                next_checkpoint += 1;
+            }
+            if next_checkpoint > previous_checkpoint
+                && !self
+                    .update_scan_status(next_checkpoint - 1, advanced_matched, epoch)
+                    .await?
+            {
+                info!("Checkpoint was set manually, abandoning current scan tick");
+                return Ok(());
+            }
+        }
+
+        // 区间末尾（或整个区间）都属于其他分片时，没有结果触发推进，在此补推检查点
+        let previous_checkpoint = next_checkpoint;
+        while completed.remove(&next_checkpoint).is_some() {
+            next_checkpoint += 1;
+        }
+        if next_checkpoint > previous_checkpoint
+            && !self
+                .update_scan_status(next_checkpoint - 1, 0, epoch)
+                .await?
+        {
+            return Ok(());
+        }
+
+        let lag = current_slot.saturating_sub(next_checkpoint - 1);
+        metrics::set_slot_lag(lag);
+        self.check_caught_up(lag);
+        Ok(())
+    }
+
+    /// 落后小于阈值时标记为已追上，并向 WebSocket 客户端推送一次 caught_up 事件
+    fn check_caught_up(&self, lag: u64) {
+        if lag >= self.catch_up_threshold_slots.max(1)
+            || self.caught_up.swap(true, Ordering::Relaxed)
+        {
+            return;
+        }
+
+        info!("Scanner caught up with the chain (lag {} slots)", lag);
+        let ws = self.ws_manager.clone();
+        tokio::spawn(async move {
+            let payload = serde_json::json!({ "type": "caught_up" });
+            ws.read().await.broadcast_status(&payload).await;
+        });
+    }
+
+    /// 该 slot 是否由本实例负责扫描
+    fn owns_slot(&self, slot: u64) -> bool {
+        slot % self.shard_count == self.shard_index
+    }
+
+    pub fn is_caught_up(&self) -> bool {
+        self.caught_up.load(Ordering::Relaxed)
+    }
+
+    /// 回填扫描 [from_slot, to_slot] 区间，不改动正向扫描的检查点。
+    /// 同一时间只允许一个回填任务，调用 cancel_backfill 后在下一个 slot 完成时停止。
+    pub async fn backfill(&self, from_slot: u64, to_slot: u64) -> Result<BackfillProgress> {
+        if from_slot > to_slot {
+            return Err(ScannerError::InvalidRequest(format!(
+                "Invalid backfill range {}..={}",
+                from_slot, to_slot
+            ))
+            .into());
+        }
+        if self
+            .backfill_running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(ScannerError::Conflict("A backfill is already running".to_string()).into());
+        }
+        self.backfill_cancelled.store(false, Ordering::SeqCst);
+
+        info!("Backfilling blocks from {} to {}", from_slot, to_slot);
+
+        let mut progress = BackfillProgress {
+            from_slot,
+            to_slot,
+            completed: 0,
+            failed: 0,
+            total: to_slot - from_slot + 1,
+            finished: false,
+            cancelled: false,
+        };
+
+        let concurrency = std::cmp::max(1, self.max_concurrent_requests);
+        let mut results = stream::iter(from_slot..=to_slot)
+            .map(|slot| async move { (slot, self.scan_block(slot).await) })
+            .buffer_unordered(concurrency);
+
+        while let Some((slot, outcome)) = results.next().await {
+            match outcome {
+                Ok(_) => {}
+                Err(e) if is_slot_skipped(&e) => {}
+                Err(e) => {
+                    error!("Error backfilling block {}: {}", slot, e);
+                    self.record_missed_slot(slot, &e.to_string()).await;
+                    progress.failed += 1;
+                }
+            }
+            progress.completed += 1;
+
+            if self.backfill_cancelled.load(Ordering::SeqCst) {
+                progress.cancelled = true;
+                break;
+            }
+            if progress
+                .completed
+                .is_multiple_of(BACKFILL_PROGRESS_INTERVAL)
+            {
+                self.dispatch_backfill_progress(progress.clone());
+            }
+        }
+        // 丢弃 stream 会取消尚未完成的 slot
+        drop(results);
+
+        progress.finished = true;
+        info!(
+            "Backfill {}..={} finished: {}/{} slots, {} failed, cancelled: {}",
+            from_slot,
+            to_slot,
+            progress.completed,
+            progress.total,
+            progress.failed,
+            progress.cancelled
+        );
+        self.dispatch_backfill_progress(progress.clone());
+        self.backfill_running.store(false, Ordering::SeqCst);
+
+        Ok(progress)
+    }
+
+    pub fn cancel_backfill(&self) -> bool {
+        if self.backfill_running.load(Ordering::SeqCst) {
+            self.backfill_cancelled.store(true, Ordering::SeqCst);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_backfill_running(&self) -> bool {
+        self.backfill_running.load(Ordering::SeqCst)
+    }
+
+    /// 退出前调用：停止回填、落盘当前扫描状态并等待 Kafka 消息发送完成
+    pub async fn shutdown(&self) {
+        self.cancel_backfill();
+
+        if let Some(status) = self.scan_status.write().await.as_mut() {
+            status.is_scanning = false;
+        }
+        if self.dry_run {
+            return;
+        }
+        if let Some(status) = self.scan_status.read().await.as_ref() {
+            let repo = ScanStatusRepo::new(self.db.clone(), &self.scan_status_id);
+            match repo.update_scan_status(status).await {
+                Ok(_) => info!("Flushed scan status at block {}", status.last_scanned_block),
+                Err(e) => error!("Failed to flush scan status: {}", e),
+            }
+        }
+
+        let Some(kafka) = self.kafka_producer.clone() else {
+            return;
+        };
+        match tokio::task::spawn_blocking(move || kafka.flush(Duration::from_secs(10))).await {
+            Ok(Ok(_)) => info!("Kafka producer drained"),
+            Ok(Err(e)) => error!("Failed to drain Kafka producer: {}", e),
+            Err(e) => error!("Kafka flush task failed: {}", e),
+        }
+    }
+
+    async fn record_missed_slot(&self, slot: u64, error: &str) {
+        if self.dry_run {
+            warn!("Dry run: not recording missed slot {}: {}", slot, error);
+            return;
+        }
+        let repo = MissedSlotsRepo::new(self.db.clone());
+        if let Err(e) = repo.record_missed_slot(slot, error).await {
+            error!("Failed to record missed slot {}: {}", slot, e);
+        }
+    }
+
+    /// 补扫之前失败的 slot，成功或确认被跳过后从 missed_slots 中移除
+    async fn retry_missed_slots(&self) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        let repo = MissedSlotsRepo::new(self.db.clone());
+        let missed = repo
+            .get_missed_slots(
+                std::cmp::max(1, self.max_concurrent_requests) as i64,
+                self.shard_index,
+                self.shard_count,
+            )
+            .await?;
+        if missed.is_empty() {
+            return Ok(());
+        }
+
+        info!("Retrying {} missed slots", missed.len());
+        for missed_slot in missed {
+            let slot = missed_slot.slot;
+            match self.scan_block(slot).await {
+                Ok(matched) => {
+                    repo.remove_missed_slot(slot).await?;
+                    if matched > 0 {
+                        let epoch = self.checkpoint_epoch.load(Ordering::SeqCst);
+                        let checkpoint = self
+                            .scan_status
+                            .read()
+                            .await
+                            .as_ref()
+                            .map(|s| s.last_scanned_block)
+                            .unwrap_or(slot);
+                        self.update_scan_status(checkpoint, matched, epoch).await?;
+                    }
+                }
+                Err(e) if is_slot_skipped(&e) => repo.remove_missed_slot(slot).await?,
+                Err(e) => {
+                    warn!(
+                        "Missed slot {} failed again (attempt {}): {}",
+                        slot,
+                        missed_slot.attempts + 1,
+                        e
+                    );
+                    repo.record_missed_slot(slot, &e.to_string()).await?;
+                }
+            }
+        }
 
         Ok(())
     }
 
-    async fn scan_block(&self, slot: u64) -> Result<()> {
+    #[instrument(name = "scan_block", skip(self), fields(matched = tracing::field::Empty))]
+    async fn scan_block(&self, slot: u64) -> Result<u64> {
         debug!("Scanning block {}", slot);
 
-        let block = self.rpc_client.get_block_with_config(
-            slot,
-            solana_client::rpc_config::RpcBlockConfig {
-                encoding: Some(UiTransactionEncoding::JsonParsed),
-                transaction_details: Some(solana_transaction_status::TransactionDetails::Full),
-                rewards: Some(false),
-                commitment: Some(CommitmentConfig::confirmed()),
-                max_supported_transaction_version: Some(0),
+        let block = self.fetch_block(slot).await?;
+
+        // 校验父区块哈希，不一致说明发生了分叉；试运行不回滚已有数据
+        if !self.dry_run {
+            if let Some(parent_hash) = self.parent_blockhash(block.parent_slot).await? {
+                if parent_hash != block.previous_blockhash {
+                    self.handle_reorg(slot, &block, &parent_hash).await?;
+                }
+            }
+        }
+
+        let matched = self.process_block(slot, block).await?;
+        metrics::record_block_scanned(matched);
+        Span::current().record("matched", matched);
+        Ok(matched)
+    }
+
+    /// 父区块哈希优先从缓存读取，未命中时查询数据库
+    async fn parent_blockhash(&self, parent_slot: u64) -> Result<Option<String>> {
+        let cached = self
+            .block_cache
+            .lock()
+            .unwrap()
+            .get(&parent_slot)
+            .map(|cached| cached.blockhash.clone());
+        if cached.is_some() {
+            return Ok(cached);
+        }
+        let hash_repo = BlockHashRepo::new(self.db.clone());
+        Ok(hash_repo
+            .get_block_hash(parent_slot)
+            .await?
+            .map(|parent| parent.blockhash))
+    }
+
+    async fn fetch_block(&self, slot: u64) -> Result<UiConfirmedBlock> {
+        let config = solana_client::rpc_config::RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::JsonParsed),
+            transaction_details: Some(solana_transaction_status::TransactionDetails::Full),
+            rewards: Some(false),
+            // getBlock 不支持 processed，最低使用 confirmed
+            commitment: Some(if self.commitment.is_at_least_confirmed() {
+                self.commitment
+            } else {
+                CommitmentConfig::confirmed()
+            }),
+            max_supported_transaction_version: Some(0),
+        };
+
+        self.rpc_with_retry("get_block", |client| async move {
+            client.get_block_with_config(slot, config).await
+        })
+        .await
+        .map_err(
+            |e| match e.downcast_ref::<ClientError>().map(classify_rpc_error) {
+                Some(RpcErrorClass::SlotSkipped) => {
+                    metrics::record_slot_skipped();
+                    ScannerError::SlotSkipped(slot).into()
+                }
+                _ => e,
             },
-        )?;
+        )
+    }
+
+    /// 执行 RPC 调用，遇到瞬时错误时按指数退避加抖动重试，最多重试 max_retries 次。
+    /// 首次使用最健康的端点，每次重试轮换到下一个端点。
+    /// 非瞬时错误（包括 slot 被跳过）直接返回原始 ClientError。
+    async fn rpc_with_retry<T, F, Fut>(&self, operation: &str, mut call: F) -> Result<T>
+    where
+        F: FnMut(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let ranked = self.rpc_pool.ranked();
+        let mut attempt = 0;
+        loop {
+            let index = ranked[attempt as usize % ranked.len()];
+            let endpoint = self.rpc_pool.endpoint(index);
+            self.rpc_pool.rate_limiter().acquire().await;
+            let error = match call(endpoint.client.clone()).await {
+                Ok(value) => {
+                    self.rpc_pool.record_success(index);
+                    return Ok(value);
+                }
+                Err(e) => e,
+            };
 
-        if let Some(transactions) = block.transactions {
+            let class = classify_rpc_error(&error);
+            if !matches!(class, RpcErrorClass::SlotSkipped) {
+                metrics::record_rpc_error();
+            }
+            match class {
+                RpcErrorClass::Transient if attempt < self.max_retries => {
+                    self.rpc_pool.record_failure(index);
+                    let delay = backoff_delay(self.retry_base_delay_ms, attempt);
+                    warn!(
+                        "RPC {} via {} failed (attempt {}/{}), retrying in {:?}: {}",
+                        operation,
+                        endpoint.url,
+                        attempt + 1,
+                        self.max_retries,
+                        delay,
+                        error
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                RpcErrorClass::Transient => {
+                    self.rpc_pool.record_failure(index);
+                    return Err(error.into());
+                }
+                _ => return Err(error.into()),
+            }
+        }
+    }
+
+    /// 处理区块内的交易，返回成功入库的关注交易数量
+    async fn process_block(&self, slot: u64, block: UiConfirmedBlock) -> Result<u64> {
+        // 交易时间使用区块时间，重扫时区块缺少时间则沿用缓存中的值；
+        // 仍然缺失时退回扫描时间并标记为估算值
+        let block_time = {
+            let mut cache = self.block_cache.lock().unwrap();
+            let block_time = block
+                .block_time
+                .or_else(|| cache.peek(&slot).and_then(|cached| cached.block_time));
+            cache.put(
+                slot,
+                CachedBlock {
+                    blockhash: block.blockhash.clone(),
+                    block_time,
+                },
+            );
+            block_time
+        };
+        let block_time = block_time.and_then(|t| Utc.timestamp_opt(t, 0).single());
+        let (timestamp, timestamp_estimated) = match block_time {
+            Some(t) => (t, false),
+            None => (Utc::now(), true),
+        };
+
+        let mut records = Vec::new();
+        if let Some(transactions) = &block.transactions {
             for tx in transactions {
-                // 这里需要正确处理交易数据
-                // 简化处理，实际需要解析transaction数据
-                if let Err(e) = self
-                    .process_transaction(slot, &tx.transaction, tx.meta.as_ref())
+                match self
+                    .process_transaction(
+                        slot,
+                        timestamp,
+                        timestamp_estimated,
+                        &tx.transaction,
+                        tx.meta.as_ref(),
+                    )
                     .await
                 {
-                    error!("Error processing transaction: {}", e);
+                    Ok(matched) => records.extend(matched),
+                    Err(e) => error!("Error processing transaction: {}", e),
                 }
             }
         }
 
-        Ok(())
+        if self.dry_run {
+            return Ok(records.len() as u64);
+        }
+
+        // 整个区块的关注交易按记录键一次批量 upsert，重复扫描覆盖已有记录
+        let mut matched = 0u64;
+        if !records.is_empty() {
+            let tx_repo = TransactionRepo::new(self.db.clone());
+            let result = tx_repo.upsert_many(&records).await;
+            matched = result.inserted.len() as u64;
+            // 只统计和推送新入库的记录，重复扫描不会重复累加或重复推送；
+            // 部分记录写入失败时，已写入的记录同样推送，否则重试时它们只会被更新而永远不会推送
+            let new_records: Vec<&Transaction> = result
+                .inserted
+                .iter()
+                .map(|&index| &records[index])
+                .collect();
+            self.update_address_stats(&new_records, false).await;
+            let mut inserted = result.inserted.iter().copied().peekable();
+            for (index, record) in records.into_iter().enumerate() {
+                if inserted.next_if_eq(&index).is_some() {
+                    self.dispatch_transaction(record);
+                }
+            }
+            if let Some((_, error)) = result.failed.first() {
+                return Err(anyhow!(
+                    "Failed to write {} records in slot {}: {}",
+                    result.failed.len(),
+                    slot,
+                    error
+                ));
+            }
+        }
+
+        // 记录区块哈希，供后续区块做分叉校验
+        let hash_repo = BlockHashRepo::new(self.db.clone());
+        hash_repo
+            .upsert_block_hash(&BlockHash::new(
+                slot,
+                block.parent_slot,
+                block.blockhash,
+                block.previous_blockhash,
+            ))
+            .await?;
+
+        Ok(matched)
     }
 
-    async fn process_transaction(
+    /// 回滚 [slot - reorg_depth, slot) 范围内的数据并重新扫描
+    async fn handle_reorg(
         &self,
         slot: u64,
-        transaction: &solana_transaction_status::EncodedTransaction,
-        meta: Option<&solana_transaction_status::UiTransactionStatusMeta>,
+        block: &UiConfirmedBlock,
+        expected_previous_blockhash: &str,
     ) -> Result<()> {
-        let watched = self.watched_addresses.read().await;
-        match transaction {
-            solana_transaction_status::EncodedTransaction::Json(ui_tx) => {
-                let signature = ui_tx.signatures.get(0).cloned().unwrap_or_default();
-                match &ui_tx.message {
-                    solana_transaction_status::UiMessage::Parsed(message) => {
-                        let account_keys: Vec<String> = message
-                            .account_keys
-                            .iter()
-                            .map(|k| k.pubkey.clone())
-                            .collect();
-                        let involved = account_keys.iter().any(|k| watched.contains(k));
-                        if !involved {
-                            return Ok(());
-                        }
-                        let fee_lamports = meta.map(|m| m.fee as f64).unwrap_or(0.0);
-                        let fee_sol = fee_lamports / 1_000_000_000f64;
-                        for instr in &message.instructions {
-                            if let solana_transaction_status::UiInstruction::Parsed(parsed_ins) =
-                                instr
-                            {
-                                match parsed_ins {
-                                    solana_transaction_status::UiParsedInstruction::Parsed(pi) => {
-                                        let program = pi.program.as_str();
-                                        let parsed_val = &pi.parsed;
-                                        if program == "system" {
-                                            if parsed_val.get("type").and_then(|v| v.as_str())
-                                                == Some("transfer")
-                                            {
-                                                if let Some(info) = parsed_val.get("info") {
-                                                    let from = info
-                                                        .get("source")
-                                                        .and_then(|v| v.as_str())
-                                                        .unwrap_or("")
-                                                        .to_string();
-                                                    let to = info
-                                                        .get("destination")
-                                                        .and_then(|v| v.as_str())
-                                                        .map(|s| s.to_string());
-                                                    let lamports = info
-                                                        .get("lamports")
-                                                        .and_then(|v| v.as_u64())
-                                                        .unwrap_or(0);
-                                                    let amount =
-                                                        (lamports as f64) / 1_000_000_000f64;
-                                                    if watched.contains(&from)
-                                                        || to
-                                                            .as_ref()
-                                                            .map(|t| watched.contains(t))
-                                                            .unwrap_or(false)
-                                                    {
-                                                        let tx_record = Transaction::new(
-                                                            signature.clone(),
-                                                            slot,
-                                                            TransactionType::Native,
-                                                            from,
-                                                            to,
-                                                            amount,
-                                                            None,
-                                                            None,
-                                                            fee_sol,
-                                                            Utc::now(),
-                                                            if meta
-                                                                .map(|m| m.err.is_none())
-                                                                .unwrap_or(false)
-                                                            {
-                                                                crate::models::TransactionStatus::Confirmed
-                                                            } else {
-                                                                crate::models::TransactionStatus::Failed
-                                                            },
-                                                            Some(parsed_val.clone()),
-                                                        );
-                                                        let tx_repo =
-                                                            TransactionRepo::new(self.db.clone());
-                                                        let _ = tx_repo
-                                                            .insert_transaction(&tx_record)
-                                                            .await;
-                                                        self.dispatch_transaction(tx_record);
-                                                    }
-                                                }
-                                            }
-                                        } else if program == "spl-token"
-                                            || program == "spl-token-2022"
-                                        {
-                                            let t = parsed_val
-                                                .get("type")
-                                                .and_then(|v| v.as_str())
-                                                .unwrap_or("");
-                                            if t == "transfer" || t == "transferChecked" {
-                                                if let Some(info) = parsed_val.get("info") {
-                                                    let from = info
-                                                        .get("source")
-                                                        .and_then(|v| v.as_str())
-                                                        .unwrap_or("")
-                                                        .to_string();
-                                                    let to = info
-                                                        .get("destination")
-                                                        .and_then(|v| v.as_str())
-                                                        .map(|s| s.to_string());
-                                                    let mint = info
-                                                        .get("mint")
-                                                        .and_then(|v| v.as_str())
-                                                        .map(|s| s.to_string());
-                                                    let amount_raw = info.get("amount");
-                                                    let decimals = info
-                                                        .get("decimals")
-                                                        .and_then(|v| v.as_u64())
-                                                        .unwrap_or(0);
-                                                    let mut amount = 0f64;
-                                                    if let Some(v) = amount_raw {
-                                                        if let Some(s) = v.as_str() {
-                                                            amount =
-                                                                s.parse::<f64>().unwrap_or(0.0);
-                                                        } else if let Some(n) = v.as_u64() {
-                                                            amount = n as f64;
-                                                        } else if let Some(n) = v.as_f64() {
-                                                            amount = n;
-                                                        }
-                                                    }
-                                                    if decimals > 0 {
-                                                        amount =
-                                                            amount / 10f64.powi(decimals as i32);
-                                                    }
-                                                    let tx_type = if decimals == 0
-                                                        && (amount - 1.0).abs() < f64::EPSILON
-                                                    {
-                                                        TransactionType::Nft
-                                                    } else {
-                                                        TransactionType::Token
-                                                    };
-                                                    if watched.contains(&from)
-                                                        || to
-                                                            .as_ref()
-                                                            .map(|t| watched.contains(t))
-                                                            .unwrap_or(false)
-                                                    {
-                                                        let tx_record = Transaction::new(
-                                                            signature.clone(),
-                                                            slot,
-                                                            tx_type,
-                                                            from,
-                                                            to,
-                                                            amount,
-                                                            mint,
-                                                            None,
-                                                            fee_sol,
-                                                            Utc::now(),
-                                                            if meta
-                                                                .map(|m| m.err.is_none())
-                                                                .unwrap_or(false)
-                                                            {
-                                                                crate::models::TransactionStatus::Confirmed
-                                                            } else {
-                                                                crate::models::TransactionStatus::Failed
-                                                            },
-                                                            Some(parsed_val.clone()),
-                                                        );
-                                                        let tx_repo =
-                                                            TransactionRepo::new(self.db.clone());
-                                                        let _ = tx_repo
-                                                            .insert_transaction(&tx_record)
-                                                            .await;
-                                                        self.dispatch_transaction(tx_record);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
+        let rollback_from = slot.saturating_sub(std::cmp::max(1, self.reorg_depth));
+        warn!(
+            "Reorg detected at slot {}: expected previous blockhash {}, got {}. Rolling back slots {}..{}",
+            slot, expected_previous_blockhash, block.previous_blockhash, rollback_from, slot
+        );
+
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        let rolled_back = tx_repo
+            .get_transactions_in_slot_range(rollback_from, slot)
+            .await?;
+        let removed_transactions = tx_repo
+            .delete_transactions_in_slot_range(rollback_from, slot)
+            .await?;
+        // 扣减被回滚交易的地址统计，重扫后会重新累加
+        self.update_address_stats(&rolled_back.iter().collect::<Vec<_>>(), true)
+            .await;
+        let hash_repo = BlockHashRepo::new(self.db.clone());
+        hash_repo
+            .delete_block_hashes_in_slot_range(rollback_from, slot)
+            .await?;
+        {
+            let mut cache = self.block_cache.lock().unwrap();
+            for rolled_back_slot in rollback_from..slot {
+                cache.pop(&rolled_back_slot);
+            }
+        }
+
+        for rescan_slot in rollback_from..slot {
+            match self.fetch_block(rescan_slot).await {
+                Ok(rescanned) => {
+                    if let Err(e) = self.process_block(rescan_slot, rescanned).await {
+                        error!("Error rescanning block {}: {}", rescan_slot, e);
                     }
-                    _ => {}
                 }
+                Err(e) => debug!(
+                    "Block {} not available during reorg rescan: {}",
+                    rescan_slot, e
+                ),
             }
-            _ => {}
         }
+
+        self.dispatch_reorg_event(ReorgEvent {
+            slot,
+            parent_slot: block.parent_slot,
+            expected_previous_blockhash: expected_previous_blockhash.to_string(),
+            actual_previous_blockhash: block.previous_blockhash.clone(),
+            rollback_from,
+            rollback_to: slot,
+            removed_transactions,
+            detected_at: Utc::now(),
+        });
+
         Ok(())
     }
 
+    /// 解析单笔交易，返回与关注地址相关的交易记录
+    #[instrument(
+        name = "process_transaction",
+        skip(self, timestamp, timestamp_estimated, transaction, meta),
+        fields(signature = tracing::field::Empty, matched = tracing::field::Empty)
+    )]
+    async fn process_transaction(
+        &self,
+        slot: u64,
+        timestamp: DateTime<Utc>,
+        timestamp_estimated: bool,
+        transaction: &EncodedTransaction,
+        meta: Option<&UiTransactionStatusMeta>,
+    ) -> Result<Vec<Transaction>> {
+        let EncodedTransaction::Json(ui_tx) = transaction else {
+            return Ok(vec![]);
+        };
+        let UiMessage::Parsed(message) = &ui_tx.message else {
+            return Ok(vec![]);
+        };
+        // 按配置跳过执行失败的交易；失败交易仍会扣除手续费，默认照常记录
+        if !self.store_failed_transactions && meta.is_some_and(|m| m.err.is_some()) {
+            debug!("Skipping failed transaction");
+            return Ok(vec![]);
+        }
+
+        // 包含 v0 交易通过地址查找表加载的账户，否则只出现在查找表中的关注地址会被漏掉
+        let watched = self.watched_addresses.read().await;
+        let account_keys = parser::account_keys(message, meta);
+        let involved = account_keys.iter().any(|k| watched.contains(*k));
+        if !involved {
+            return Ok(vec![]);
+        }
+
+        let signature = ui_tx.signatures.first().cloned().unwrap_or_default();
+        let span = Span::current();
+        span.record("signature", signature.as_str());
+        let fee_lamports = meta.map(|m| m.fee as f64).unwrap_or(0.0);
+        let fee_sol = fee_lamports / 1_000_000_000f64;
+        let status = if meta.map(|m| m.err.is_none()).unwrap_or(false) {
+            TransactionStatus::Confirmed
+        } else {
+            TransactionStatus::Failed
+        };
+
+        // 优先费由 ComputeBudget 指令计算，CU 消耗优先取 meta 中的实际值
+        let compute_budget = parser::parse_compute_budget(message);
+        let priority_fee = compute_budget.as_ref().and_then(|b| b.priority_fee());
+        let compute_units = compute_budget.as_ref().map(|budget| {
+            meta.and_then(|m| Option::<u64>::from(m.compute_units_consumed.clone()))
+                .unwrap_or_else(|| budget.effective_unit_limit())
+        });
+
+        let memo = parser::parse_memo(message, meta);
+
+        // 顶层指令和 CPI 内部指令使用同一套匹配逻辑，归属于同一个签名和 slot
+        let balances = parser::TokenBalances::new(message, meta);
+        let mut matched = Vec::new();
+        let mut dust_skipped = false;
+        for positioned in parser::collect_parsed_instructions(message, meta) {
+            let instruction = positioned.instruction;
+            if !self.program_allowed(&instruction.program_id) {
+                debug!(program = %instruction.program_id, "Instruction filtered by program list");
+                continue;
+            }
+            let Some(transfer) = parser::parse_transfer(instruction, &balances)
+                .or_else(|| parser::parse_account_event(instruction))
+            else {
+                continue;
+            };
+            if !transfer.involves(&watched) {
+                continue;
+            }
+            if transfer.is_dust(self.min_lamports, self.min_token_amount) {
+                debug!(
+                    transaction_type = transfer.transaction_type.as_str(),
+                    amount = transfer.amount,
+                    "Skipping dust transfer"
+                );
+                dust_skipped = true;
+                continue;
+            }
+            debug!(
+                program = %instruction.program_id,
+                transaction_type = transfer.transaction_type.as_str(),
+                "Matched transfer instruction"
+            );
+
+            let (token_symbol, token_decimals, transaction_type, decimals) =
+                self.classify_transfer(&transfer).await;
+            let watched_address = transfer.watched_address(&watched);
+            let mut tx_record = Transaction::new(
+                signature.clone(),
+                slot,
+                transaction_type,
+                transfer.from,
+                transfer.to,
+                parser::ui_amount(transfer.amount_raw, decimals),
+                transfer.mint,
+                token_symbol,
+                token_decimals,
+                fee_sol,
+                timestamp,
+                status.clone(),
+                Some(transfer.raw),
+            );
+            tx_record.set_position(&positioned.position());
+            tx_record.watched_address = watched_address;
+            tx_record.amount_raw = transfer.amount_raw;
+            tx_record.decimals = decimals;
+            tx_record.timestamp_estimated = timestamp_estimated;
+            tx_record.priority_fee = priority_fee;
+            tx_record.compute_units = compute_units;
+            tx_record.memo = memo.clone();
+            matched.push(tx_record);
+        }
+
+        // 涉及关注地址但没有可识别的转账指令时，仍保留一条 Unknown 记录并附带程序 id
+        let program_ids: Vec<String> = parser::program_ids(message)
+            .into_iter()
+            .filter(|program_id| self.program_allowed(program_id))
+            .collect();
+        if matched.is_empty() {
+            debug!(
+                programs = ?program_ids,
+                "Watched address involved but no instruction matched"
+            );
+        }
+        // 只包含粉尘转账的交易整体忽略，不再退化为 Unknown 记录
+        if matched.is_empty() && !dust_skipped && !program_ids.is_empty() {
+            let fee_payer = account_keys
+                .first()
+                .map(|k| k.to_string())
+                .unwrap_or_default();
+            let watched_accounts: Vec<String> = account_keys
+                .iter()
+                .filter(|k| watched.contains(**k))
+                .map(|k| k.to_string())
+                .collect();
+            let to_address = watched_accounts
+                .iter()
+                .find(|account| **account != fee_payer)
+                .cloned();
+            let raw_data = serde_json::json!({
+                "program_ids": program_ids,
+                "watched_accounts": watched_accounts,
+            });
+
+            let mut tx_record = Transaction::new(
+                signature,
+                slot,
+                TransactionType::Unknown,
+                fee_payer,
+                to_address,
+                0.0,
+                None,
+                None,
+                None,
+                fee_sol,
+                timestamp,
+                status,
+                Some(raw_data),
+            );
+            tx_record.watched_address = watched_accounts.first().cloned();
+            tx_record.timestamp_estimated = timestamp_estimated;
+            tx_record.priority_fee = priority_fee;
+            tx_record.compute_units = compute_units;
+            tx_record.memo = memo;
+            matched.push(tx_record);
+        }
+
+        span.record("matched", matched.len());
+        if self.dry_run {
+            for tx in &matched {
+                info!(
+                    transaction_type = tx.transaction_type.as_str(),
+                    from = %tx.from_address,
+                    to = tx.to_address.as_deref().unwrap_or(""),
+                    amount = tx.amount,
+                    mint = tx.token_mint.as_deref().unwrap_or(""),
+                    "Dry run: would record transaction"
+                );
+            }
+        }
+        Ok(matched)
+    }
+
+    /// 程序白名单/黑名单过滤，未配置时全部放行
+    fn program_allowed(&self, program_id: &str) -> bool {
+        if self.program_denylist.contains(program_id) {
+            return false;
+        }
+        match &self.program_allowlist {
+            Some(allowlist) => allowlist.contains(program_id),
+            None => true,
+        }
+    }
+
+    /// 代币交易补充 symbol，精度优先使用已知值；账户事件只记录 mint，不重新分类。
+    /// 返回 (symbol, 代币精度, 交易类型, amount_raw 的精度)
+    async fn classify_transfer(
+        &self,
+        transfer: &parser::ParsedTransfer,
+    ) -> (Option<String>, Option<u8>, TransactionType, u8) {
+        let (token_symbol, token_decimals, transaction_type) = match &transfer.mint {
+            Some(mint) if transfer.transaction_type != TransactionType::Account => {
+                self.classify_token(mint, transfer.decimals, transfer.transaction_type)
+                    .await
+            }
+            _ => (None, None, transfer.transaction_type),
+        };
+
+        // 展示金额统一由原始单位换算；精度在解析时未知、由 mint 信息补全时也按补全后的精度换算
+        let decimals = match transfer.transaction_type {
+            TransactionType::Native | TransactionType::Account => parser::SOL_DECIMALS,
+            _ => token_decimals.unwrap_or(0),
+        };
+        (token_symbol, token_decimals, transaction_type, decimals)
+    }
+
+    /// 按 mint 的供应量和精度判断是否为 NFT，mint 信息不可用时沿用 fallback；
+    /// 返回 (symbol, decimals, 交易类型)，已知精度优先于 mint 信息中的精度
+    async fn classify_token(
+        &self,
+        mint: &str,
+        decimals: Option<u8>,
+        fallback: TransactionType,
+    ) -> (Option<String>, Option<u8>, TransactionType) {
+        let metadata = self.token_metadata.get_metadata(mint).await;
+        let transaction_type = match metadata.is_nft() {
+            Some(true) => TransactionType::Nft,
+            Some(false) => TransactionType::Token,
+            None => fallback,
+        };
+        (
+            metadata.symbol,
+            decimals.or(metadata.decimals),
+            transaction_type,
+        )
+    }
+
     fn dispatch_transaction(&self, tx: Transaction) {
+        let sinks = self.sinks.clone();
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            emit_to_sinks(&sinks, db, &tx).await;
+        });
+    }
+
+    /// 重新处理已存储的交易：用当前的解析逻辑重新解析 raw_data 并按当前的 mint 信息重新分类，
+    /// 有变化的记录写回数据库并重新投递到 Kafka（WebSocket、Webhook 只推送实时交易）
+    pub async fn reprocess_transactions(
+        &self,
+        query: &TransactionQuery,
+    ) -> Result<ReprocessResult> {
+        let repo = TransactionRepo::new(self.db.clone());
+        let mut cursor = repo.stream_all(query).await?;
+        let mut result = ReprocessResult::default();
+        let kafka_sinks: Vec<Box<dyn TransactionSink>> = self
+            .kafka_producer
+            .iter()
+            .map(|kafka| Box::new(kafka.clone()) as Box<dyn TransactionSink>)
+            .collect();
+
+        while let Some(tx) = cursor.try_next().await? {
+            result.scanned += 1;
+            if result.scanned % REPROCESS_LOG_INTERVAL == 0 {
+                info!(
+                    "Reprocess progress: scanned {}, updated {}, failed {}",
+                    result.scanned, result.updated, result.failed
+                );
+            }
+
+            // Unknown 记录没有保存指令，无法重放，需要重新扫描对应 slot
+            let Some(mut transfer) = tx
+                .raw_data
+                .as_ref()
+                .and_then(|raw| parser::reparse_raw(tx.transaction_type, raw))
+            else {
+                continue;
+            };
+            // 重放时没有交易内余额，指令中缺少的 mint 与精度沿用扫描时记录的值
+            if matches!(
+                transfer.transaction_type,
+                TransactionType::Token | TransactionType::Nft
+            ) {
+                transfer.mint = transfer.mint.or_else(|| tx.token_mint.clone());
+                transfer.decimals = transfer.decimals.or(tx.token_decimals);
+                let decimals = transfer.decimals.unwrap_or(0);
+                transfer.transaction_type = parser::token_transaction_type(
+                    parser::ui_amount(transfer.amount_raw, decimals),
+                    decimals as u64,
+                );
+            }
+            let (symbol, token_decimals, transaction_type, decimals) =
+                self.classify_transfer(&transfer).await;
+            let symbol = symbol.or_else(|| tx.token_symbol.clone());
+
+            let mut updated = tx.clone();
+            updated.transaction_type = transaction_type;
+            updated.from_address = transfer.from;
+            updated.to_address = transfer.to;
+            updated.amount = parser::ui_amount(transfer.amount_raw, decimals);
+            updated.amount_raw = transfer.amount_raw;
+            updated.decimals = decimals;
+            updated.token_mint = transfer.mint;
+            updated.token_symbol = symbol;
+            updated.token_decimals = token_decimals;
+            updated.raw_data = Some(transfer.raw);
+            let fields = |t: &Transaction| {
+                (
+                    t.transaction_type,
+                    t.from_address.clone(),
+                    t.to_address.clone(),
+                    t.amount_raw,
+                    t.decimals,
+                    t.token_mint.clone(),
+                    t.token_symbol.clone(),
+                    t.token_decimals,
+                    t.raw_data.clone(),
+                )
+            };
+            if fields(&updated) == fields(&tx) {
+                continue;
+            }
+
+            debug!(
+                signature = %tx.signature,
+                from = tx.transaction_type.as_str(),
+                to = updated.transaction_type.as_str(),
+                "Reprocessed transaction"
+            );
+            let tx = updated;
+
+            if self.dry_run {
+                info!("Dry run: would update transaction {}", tx.signature);
+                result.updated += 1;
+                continue;
+            }
+            match repo.replace_transaction(&tx).await {
+                Ok(_) => {
+                    emit_to_sinks(&kafka_sinks, self.db.clone(), &tx).await;
+                    result.updated += 1;
+                }
+                Err(e) => {
+                    error!("Failed to update transaction {}: {}", tx.signature, e);
+                    result.failed += 1;
+                }
+            }
+        }
+
+        info!(
+            "Reprocess finished: scanned {}, updated {}, failed {}",
+            result.scanned, result.updated, result.failed
+        );
+        Ok(result)
+    }
+
+    /// 按关注地址累加收发统计；revert 为 true 时扣减，用于分叉回滚
+    async fn update_address_stats(&self, records: &[&Transaction], revert: bool) {
+        let mut deltas: HashMap<&str, (f64, f64, i64)> = HashMap::new();
+        {
+            let watched = self.watched_addresses.read().await;
+            for tx in records {
+                let native_amount = if tx.transaction_type == TransactionType::Native {
+                    tx.amount
+                } else {
+                    0.0
+                };
+                if watched.contains(&tx.from_address) {
+                    let delta = deltas.entry(tx.from_address.as_str()).or_default();
+                    delta.1 += native_amount;
+                    delta.2 += 1;
+                }
+                if let Some(to) = tx.to_address.as_deref().filter(|to| watched.contains(*to)) {
+                    let delta = deltas.entry(to).or_default();
+                    delta.0 += native_amount;
+                    // 转给自己只计一笔交易
+                    if to != tx.from_address {
+                        delta.2 += 1;
+                    }
+                }
+            }
+        }
+
+        let sign = if revert { -1.0 } else { 1.0 };
+        let repo = AddressStatsRepo::new(self.db.clone());
+        for (address, (received, sent, tx_count)) in deltas {
+            if let Err(e) = repo
+                .increment(
+                    address,
+                    sign * received,
+                    sign * sent,
+                    sign as i64 * tx_count,
+                    !revert,
+                )
+                .await
+            {
+                error!("Failed to update address stats for {}: {}", address, e);
+            }
+        }
+    }
+
+    fn dispatch_reorg_event(&self, event: ReorgEvent) {
         let kafka = self.kafka_producer.clone();
         let ws = self.ws_manager.clone();
         tokio::spawn(async move {
-            let _ = kafka.send_transaction(&tx).await;
-            let _ = ws.read().await.broadcast_transaction(&tx).await;
+            if let Some(kafka) = kafka {
+                let _ = kafka.send_event(&event.slot.to_string(), &event).await;
+            }
+            let payload = serde_json::json!({ "type": "reorg", "data": event });
+            ws.read().await.broadcast_event(&payload).await;
         });
     }
 
-    async fn update_scan_status(&self, last_block: u64) -> Result<()> {
-        let repo = ScanStatusRepo::new(self.db.clone());
+    fn dispatch_backfill_progress(&self, progress: BackfillProgress) {
+        if self.dry_run {
+            return;
+        }
+        let ws = self.ws_manager.clone();
+        tokio::spawn(async move {
+            let payload = serde_json::json!({ "type": "backfill_progress", "data": progress });
+            ws.read().await.broadcast_event(&payload).await;
+        });
+    }
 
-        let scan_status = ScanStatus::new(last_block);
-        let _ = repo.update_scan_status(&scan_status).await;
+    /// 推进检查点并累加新入库的交易数；epoch 不一致（期间手动设置过检查点）时不写入并返回 false，试运行时只在内存中推进
+    async fn update_scan_status(
+        &self,
+        last_block: u64,
+        matched_count: u64,
+        epoch: u64,
+    ) -> Result<bool> {
+        let repo = ScanStatusRepo::new(self.db.clone(), &self.scan_status_id);
 
+        // 持有写锁直到落库，避免并发更新丢失计数
         let mut current_status = self.scan_status.write().await;
+        if self.checkpoint_epoch.load(Ordering::SeqCst) != epoch {
+            return Ok(false);
+        }
+        let mut scan_status =
+            ScanStatus::advance(current_status.as_ref(), last_block, matched_count);
+        scan_status.id = self.scan_status_id.clone();
+        if self.dry_run {
+            *current_status = Some(scan_status);
+            return Ok(true);
+        }
+        // 落库失败时不推进内存中的检查点，也不推送进度
+        repo.update_scan_status(&scan_status).await?;
+
+        let checkpoint = scan_status.last_scanned_block;
         *current_status = Some(scan_status);
+        drop(current_status);
+        self.dispatch_scan_progress(checkpoint);
 
-        Ok(())
+        Ok(true)
     }
 
-    pub async fn add_watched_address(&self, address: String) -> Result<()> {
-        let mut watched = self.watched_addresses.write().await;
-        watched.insert(address.clone());
+    /// 手动覆盖检查点（可回退或前移），返回原检查点；下一轮扫描从 slot + 1 开始
+    pub async fn set_checkpoint(&self, slot: u64) -> Result<Option<u64>> {
+        let mut current_status = self.scan_status.write().await;
+        let previous = if self.dry_run {
+            current_status.as_ref().map(|s| s.last_scanned_block)
+        } else {
+            ScanStatusRepo::new(self.db.clone(), &self.scan_status_id)
+                .set_checkpoint(slot)
+                .await?
+        };
+        self.checkpoint_epoch.fetch_add(1, Ordering::SeqCst);
+
+        let mut status = ScanStatus::new(slot);
+        status.id = self.scan_status_id.clone();
+        if let Some(old) = current_status.as_ref() {
+            status.total_transactions_scanned = old.total_transactions_scanned;
+            status.is_scanning = old.is_scanning;
+        }
+        *current_status = Some(status);
+        warn!("Scan checkpoint set to {} (previous: {:?})", slot, previous);
+
+        Ok(previous)
+    }
+
+    /// 重放一批 Kafka 死信：按失败时间顺序重新投递，成功的从死信集合中删除。
+    /// 遇到投递失败即停止本批，剩余死信留待下次重放
+    pub async fn replay_dead_letters(&self, limit: u32) -> Result<KafkaReplayResult> {
+        let Some(kafka) = self.kafka_producer.clone() else {
+            return Err(ScannerError::InvalidRequest("Kafka is not enabled".to_string()).into());
+        };
+        let repo = DeadLetterRepo::new(self.db.clone());
+        let mut result = KafkaReplayResult::default();
+
+        for dead_letter in repo.get_dead_letters(limit).await? {
+            let Some(id) = dead_letter.id else {
+                continue;
+            };
+            if let Err(e) = kafka.send_transaction(&dead_letter.transaction).await {
+                warn!(
+                    "Failed to replay dead letter {}: {}",
+                    dead_letter.transaction.signature, e
+                );
+                break;
+            }
+            repo.delete_dead_letter(id).await?;
+            result.replayed += 1;
+        }
+
+        result.remaining = repo.count_dead_letters().await?;
+        info!(
+            "Replayed {} Kafka dead letters, {} remaining",
+            result.replayed, result.remaining
+        );
+        Ok(result)
+    }
+
+    /// 向订阅了扫描进度的 WebSocket 连接推送当前检查点，最多每秒一次
+    fn dispatch_scan_progress(&self, slot: u64) {
+        {
+            let mut last_push = self
+                .last_progress_push
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if last_push.is_some_and(|at| at.elapsed() < SCAN_PROGRESS_INTERVAL) {
+                return;
+            }
+            *last_push = Some(Instant::now());
+        }
+
+        let lag = self
+            .latest_slot
+            .load(Ordering::Relaxed)
+            .saturating_sub(slot);
+        let ws = self.ws_manager.clone();
+        tokio::spawn(async move {
+            let payload = serde_json::json!({ "type": "scan_progress", "slot": slot, "lag": lag });
+            ws.read().await.broadcast_status(&payload).await;
+        });
+    }
+
+    /// 汇总扫描进度与依赖状态，落后过多或依赖不可用时视为不健康
+    pub async fn health(&self) -> HealthDetail {
+        let (last_scanned_block, is_scanning) = match self.scan_status.read().await.as_ref() {
+            Some(status) => (Some(status.last_scanned_block), status.is_scanning),
+            None => (None, false),
+        };
+
+        // 健康检查只请求一次最健康的端点，不走重试退避
+        let endpoint = self.rpc_pool.endpoint(self.rpc_pool.ranked()[0]);
+        self.rpc_pool.rate_limiter().acquire().await;
+        let current_slot = endpoint.client.get_slot().await.ok();
+        let lag = current_slot
+            .zip(last_scanned_block)
+            .map(|(current, last)| current.saturating_sub(last));
+
+        let mongodb_connected = self
+            .db
+            .run_command(mongodb::bson::doc! { "ping": 1 }, None)
+            .await
+            .is_ok();
+
+        // 未启用 Kafka 时不影响健康状态
+        let kafka_enabled = self.kafka_producer.is_some();
+        let kafka_circuit = self
+            .kafka_producer
+            .as_ref()
+            .map(|kafka| kafka.circuit_state());
+        let kafka_reachable = match self.kafka_producer.clone() {
+            Some(kafka) => tokio::task::spawn_blocking(move || kafka.ping(Duration::from_secs(2)))
+                .await
+                .map(|result| result.is_ok())
+                .unwrap_or(false),
+            None => false,
+        };
+        let kafka_ok =
+            !kafka_enabled || (kafka_reachable && kafka_circuit != Some(CircuitState::Open));
+
+        // 待命实例不推进检查点，不按落后程度判断健康
+        let role = self.role();
+        let lag_ok =
+            role == ScannerRole::Standby || lag.is_some_and(|lag| lag <= self.health_max_slot_lag);
+        let healthy = mongodb_connected && kafka_ok && lag_ok;
+
+        HealthDetail {
+            healthy,
+            last_scanned_block,
+            current_slot,
+            lag,
+            is_scanning,
+            mongodb_connected,
+            kafka_enabled,
+            kafka_reachable,
+            kafka_circuit,
+            caught_up: self.is_caught_up(),
+            role,
+            min_lamports: self.min_lamports,
+            min_token_amount: self.min_token_amount,
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            programs: parser::supported_programs(),
+            transaction_types: vec![
+                TransactionType::Native,
+                TransactionType::Token,
+                TransactionType::Nft,
+                TransactionType::Account,
+                TransactionType::Unknown,
+            ],
+            commitment: self.configured_commitment,
+            scan_mode: self.scan_mode,
+        }
+    }
+
+    pub async fn info(&self) -> ServiceInfo {
+        let watched_addresses = self.watched_addresses.read().await.len();
+        let ws = self.ws_manager.read().await;
+        ServiceInfo {
+            watched_addresses,
+            ws_connections: ws.connection_count().await,
+            ws_subscribed_addresses: ws.subscribed_address_count().await,
+        }
+    }
+
+    pub async fn add_watched_address(&self, address: String, label: Option<String>) -> Result<()> {
+        validate_address(&address)?;
 
         let repo = WalletAddressRepo::new(self.db.clone());
-        let _ = repo.insert_address(&address, None).await;
+        let mut watched = self.watched_addresses.write().await;
+        if !watched.contains(&address) {
+            // 以数据库中的有效地址数为准，重启后依然生效
+            let active = repo.count_active_addresses().await?;
+            if active >= self.max_addresses as u64 {
+                return Err(ScannerError::LimitExceeded(format!(
+                    "at most {} watched addresses are allowed",
+                    self.max_addresses
+                ))
+                .into());
+            }
+        }
+        repo.insert_address(&address, label.as_deref()).await?;
+        watched.insert(address.clone());
+        drop(watched);
 
+        self.dispatch_address_event(AddressEvent::new(AddressAction::Added, address, label));
         Ok(())
     }
 
-    pub async fn remove_watched_address(&self, address: String) -> Result<()> {
+    /// 停止关注地址，返回 false 表示该地址本就未被关注
+    pub async fn remove_watched_address(&self, address: String) -> Result<bool> {
         let mut watched = self.watched_addresses.write().await;
-        watched.remove(&address);
+        let removed = watched.remove(&address);
 
         let repo = WalletAddressRepo::new(self.db.clone());
-        let _ = repo.deactivate_address(&address).await;
+        let deactivated = repo.deactivate_address(&address).await?;
+        drop(watched);
 
-        Ok(())
+        if removed || deactivated {
+            self.dispatch_address_event(AddressEvent::new(AddressAction::Removed, address, None));
+        }
+        Ok(removed || deactivated)
     }
 
-    pub async fn get_watched_addresses(&self) -> Vec<String> {
-        let watched = self.watched_addresses.read().await;
-        watched.iter().cloned().collect()
+    /// 后台投递地址变更事件，发送失败只记录日志，不影响接口结果
+    fn dispatch_address_event(&self, event: AddressEvent) {
+        let Some(kafka) = self.kafka_producer.clone() else {
+            return;
+        };
+        tokio::spawn(async move {
+            let _ = kafka.send_address_event(&event).await;
+        });
+    }
+
+    pub async fn get_address_stats(&self, address: String) -> Result<AddressStats> {
+        validate_address(&address)?;
+        let repo = AddressStatsRepo::new(self.db.clone());
+        let stats = repo.get_stats(&address).await?;
+        Ok(stats.unwrap_or_else(|| AddressStats::empty(address)))
+    }
+
+    pub async fn get_watched_addresses(&self) -> Result<Vec<WalletAddress>> {
+        let repo = WalletAddressRepo::new(self.db.clone());
+        repo.get_all_active_addresses().await
+    }
+
+    pub async fn get_watched_addresses_page(&self, query: AddressQuery) -> Result<AddressList> {
+        let repo = WalletAddressRepo::new(self.db.clone());
+        let (addresses, total) = tokio::try_join!(
+            repo.get_active_addresses_paged(&query),
+            repo.count_addresses(&query)
+        )?;
+        Ok(AddressList {
+            addresses,
+            total,
+            limit: query.effective_limit(),
+            offset: query.offset.unwrap_or(0),
+        })
+    }
+
+    pub async fn get_transactions(&self, query: TransactionQuery) -> Result<TransactionList> {
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        let (data, total) = tokio::try_join!(
+            tx_repo.get_transactions(&query),
+            tx_repo.count_transactions(&query)
+        )?;
+        Ok(TransactionList {
+            data,
+            total,
+            limit: query.limit,
+            offset: query.offset.unwrap_or(0),
+        })
+    }
+
+    pub async fn export_transactions(
+        &self,
+        query: TransactionQuery,
+    ) -> Result<mongodb::Cursor<Transaction>> {
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        tx_repo.stream_transactions(&query).await
+    }
+
+    /// key 为记录键或签名，签名对应多条记录时返回第一条
+    pub async fn get_transaction(&self, key: &str) -> Result<Transaction> {
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        tx_repo
+            .get_transaction_by_key(key)
+            .await?
+            .ok_or_else(|| ScannerError::TransactionNotFound(key.to_string()).into())
+    }
+
+    /// 交易的原始指令 JSON，没有保存时返回 null
+    pub async fn get_transaction_raw(&self, key: &str) -> Result<serde_json::Value> {
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        let raw_data = tx_repo
+            .get_raw_data(key)
+            .await?
+            .ok_or_else(|| ScannerError::TransactionNotFound(key.to_string()))?;
+        Ok(raw_data.unwrap_or(serde_json::Value::Null))
+    }
+
+    pub async fn get_stats(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<StatsSummary> {
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        tx_repo.aggregate_stats(start_time, end_time).await
     }
 
-    pub async fn get_transactions(
+    pub async fn get_timeseries(
         &self,
         address: Option<String>,
-        limit: Option<u32>,
-        offset: Option<u32>,
-    ) -> Result<Vec<Transaction>> {
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        interval: TimeInterval,
+    ) -> Result<Vec<TimeBucket>> {
+        let tx_repo = TransactionRepo::new(self.db.clone());
+        tx_repo
+            .count_by_interval(address.as_deref(), start_time, end_time, interval)
+            .await
+    }
+
+    pub async fn get_transactions_page(&self, query: TransactionQuery) -> Result<TransactionPage> {
         let tx_repo = TransactionRepo::new(self.db.clone());
-        let _ = tx_repo.get_transactions(address, limit, offset).await;
-        Ok(vec![])
+        tx_repo
+            .get_transactions_cursor(&query, query.cursor.as_deref(), query.limit.unwrap_or(0))
+            .await
+    }
+}
+
+fn is_slot_skipped(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ScannerError>(),
+        Some(ScannerError::SlotSkipped(_))
+    )
+}
+
+/// 依次投递到所有输出；支持重放的输出（如 Kafka）失败时写入死信集合，避免静默丢失
+async fn emit_to_sinks(sinks: &[Box<dyn TransactionSink>], db: Database, tx: &Transaction) {
+    for sink in sinks {
+        let Err(e) = sink.emit(tx).await else {
+            continue;
+        };
+        error!(
+            "Failed to emit transaction {} to {}: {}",
+            tx.signature,
+            sink.name(),
+            e
+        );
+        let Some(topic) = sink.dead_letter_topic(tx) else {
+            continue;
+        };
+        let dead_letter = KafkaDeadLetter {
+            id: None,
+            transaction: tx.clone(),
+            topic,
+            error: e.to_string(),
+            failed_at: Utc::now(),
+        };
+        if let Err(e) = DeadLetterRepo::new(db.clone())
+            .insert_dead_letter(&dead_letter)
+            .await
+        {
+            error!(
+                "Failed to record Kafka dead letter for {}: {}",
+                tx.signature, e
+            );
+        }
     }
 }
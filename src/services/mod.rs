@@ -1,2 +1,6 @@
 pub mod blockchain;
+pub mod parser;
+pub mod rpc_pool;
+pub mod sink;
+pub mod token_metadata;
 pub mod websocket;
@@ -0,0 +1,613 @@
+use solana_transaction_status::parse_instruction::ParsedInstruction;
+use solana_transaction_status::{
+    UiInstruction, UiLoadedAddresses, UiParsedInstruction, UiParsedMessage,
+    UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{ProgramCapability, TransactionType};
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000f64;
+/// SOL 金额（lamports）的精度
+pub const SOL_DECIMALS: u8 = 9;
+
+const MEMO_PROGRAM_IDS: [&str; 2] = [
+    "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+    "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo",
+];
+
+const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+
+// 解析转账指令的程序：(jsonParsed 中的程序名, 程序 id)
+const TRANSFER_PROGRAMS: [(&str, &str); 3] = [
+    ("system", "11111111111111111111111111111111"),
+    ("spl-token", "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+    (
+        "spl-token-2022",
+        "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+    ),
+];
+// ComputeBudgetInstruction 的 borsh 枚举下标
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+// 未设置 compute unit limit 时，每条指令默认 20 万 CU，单笔交易上限 140 万
+const DEFAULT_UNITS_PER_INSTRUCTION: u64 = 200_000;
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+const MICRO_LAMPORTS_PER_LAMPORT: f64 = 1_000_000f64;
+
+/// ComputeBudget 指令中设置的 CU 上限与单价
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    /// 每个 CU 的价格，单位 micro-lamports
+    pub unit_price: Option<u64>,
+    instruction_count: usize,
+}
+
+impl ComputeBudget {
+    /// 实际生效的 CU 上限：显式设置的值，否则按非 ComputeBudget 指令数估算
+    pub fn effective_unit_limit(&self) -> u64 {
+        self.unit_limit.map(u64::from).unwrap_or_else(|| {
+            (self.instruction_count as u64 * DEFAULT_UNITS_PER_INSTRUCTION)
+                .min(MAX_COMPUTE_UNIT_LIMIT)
+        })
+    }
+
+    /// 优先费（SOL）= CU 单价 × CU 上限
+    pub fn priority_fee(&self) -> Option<f64> {
+        let price = self.unit_price?;
+        let micro_lamports = price as f64 * self.effective_unit_limit() as f64;
+        Some((micro_lamports / MICRO_LAMPORTS_PER_LAMPORT).ceil() / LAMPORTS_PER_SOL)
+    }
+}
+
+/// 从单条已解析指令中提取出的转账信息
+#[derive(Debug, Clone)]
+pub struct ParsedTransfer {
+    pub transaction_type: TransactionType,
+    pub from: String,
+    pub to: Option<String>,
+    pub amount: f64,
+    /// 链上原始单位的金额：SOL 为 lamports，代币为最小单位
+    pub amount_raw: u64,
+    pub mint: Option<String>,
+    pub decimals: Option<u8>,
+    pub raw: serde_json::Value,
+}
+
+impl ParsedTransfer {
+    pub fn involves(&self, watched: &HashSet<String>) -> bool {
+        watched.contains(&self.from)
+            || self
+                .to
+                .as_ref()
+                .map(|t| watched.contains(t))
+                .unwrap_or(false)
+    }
+
+    /// 命中的关注地址，发送方和接收方都在关注列表中时取发送方
+    pub fn watched_address(&self, watched: &HashSet<String>) -> Option<String> {
+        std::iter::once(&self.from)
+            .chain(self.to.as_ref())
+            .find(|address| watched.contains(*address))
+            .cloned()
+    }
+
+    /// 链上原始单位的金额：SOL 转账为 lamports，代币为最小单位
+    pub fn base_units(&self) -> u64 {
+        self.amount_raw
+    }
+
+    /// 低于阈值的小额转账视为粉尘，阈值为 0 表示不过滤；账户事件不参与粉尘过滤
+    pub fn is_dust(&self, min_lamports: u64, min_token_amount: u64) -> bool {
+        let threshold = match self.transaction_type {
+            TransactionType::Account => return false,
+            TransactionType::Native => min_lamports,
+            _ => min_token_amount,
+        };
+        threshold > 0 && self.base_units() < threshold
+    }
+}
+
+/// 交易涉及的全部账户：消息中的静态账户，加上 v0 交易通过地址查找表加载的账户
+/// （先可写后只读，与代币余额中 account_index 的编号顺序一致）。
+/// jsonParsed 编码可能已将加载的账户并入 account_keys，按首次出现去重。
+pub fn account_keys<'a>(
+    message: &'a UiParsedMessage,
+    meta: Option<&'a UiTransactionStatusMeta>,
+) -> Vec<&'a str> {
+    let loaded: Option<&UiLoadedAddresses> =
+        meta.and_then(|m| Option::from(m.loaded_addresses.as_ref()));
+    let loaded_keys = loaded
+        .into_iter()
+        .flat_map(|loaded| loaded.writable.iter().chain(loaded.readonly.iter()));
+
+    let mut seen = HashSet::new();
+    message
+        .account_keys
+        .iter()
+        .map(|k| k.pubkey.as_str())
+        .chain(loaded_keys.map(String::as_str))
+        .filter(|key| seen.insert(*key))
+        .collect()
+}
+
+/// 交易前后的代币余额，按账户在 account_keys 中的索引查找
+#[derive(Debug, Default)]
+pub struct TokenBalances<'a> {
+    account_keys: Vec<&'a str>,
+    pre: &'a [UiTransactionTokenBalance],
+    post: &'a [UiTransactionTokenBalance],
+    /// 每个代币账户在交易中出现的转账指令数
+    transfer_counts: HashMap<&'a str, usize>,
+}
+
+impl<'a> TokenBalances<'a> {
+    pub fn new(message: &'a UiParsedMessage, meta: Option<&'a UiTransactionStatusMeta>) -> Self {
+        let pre = meta
+            .and_then(|m| Option::from(m.pre_token_balances.as_ref()))
+            .map(|b: &Vec<_>| b.as_slice())
+            .unwrap_or_default();
+        let post = meta
+            .and_then(|m| Option::from(m.post_token_balances.as_ref()))
+            .map(|b: &Vec<_>| b.as_slice())
+            .unwrap_or_default();
+
+        let mut transfer_counts = HashMap::new();
+        for positioned in collect_parsed_instructions(message, meta) {
+            let Some(info) = token_transfer_info(positioned.instruction) else {
+                continue;
+            };
+            for field in ["source", "destination"] {
+                if let Some(account) = info.get(field).and_then(|v| v.as_str()) {
+                    *transfer_counts.entry(account).or_insert(0) += 1;
+                }
+            }
+        }
+
+        Self {
+            account_keys: account_keys(message, meta),
+            pre,
+            post,
+            transfer_counts,
+        }
+    }
+
+    fn find(
+        &self,
+        balances: &'a [UiTransactionTokenBalance],
+        account: &str,
+    ) -> Option<&'a UiTransactionTokenBalance> {
+        let index = self.account_keys.iter().position(|k| *k == account)?;
+        balances.iter().find(|b| b.account_index as usize == index)
+    }
+
+    /// 代币账户对应的 mint 与精度
+    pub fn mint_and_decimals(&self, account: &str) -> Option<(String, u8)> {
+        self.find(self.post, account)
+            .or_else(|| self.find(self.pre, account))
+            .map(|b| (b.mint.clone(), b.ui_token_amount.decimals))
+    }
+
+    /// 代币账户在交易前后的余额变化量（最小单位，未按精度换算）。
+    /// 只有该账户在交易中只参与一笔转账时余额变化才等于转账金额，否则返回 None
+    pub fn raw_delta(&self, account: &str) -> Option<u64> {
+        if self.transfer_counts.get(account).copied().unwrap_or(0) > 1 {
+            return None;
+        }
+        let post = self.find(self.post, account);
+        let pre = self.find(self.pre, account);
+        post.or(pre)?;
+        let raw = |b: Option<&UiTransactionTokenBalance>| {
+            b.and_then(|b| b.ui_token_amount.amount.parse::<i128>().ok())
+                .unwrap_or(0)
+        };
+        let change = (raw(post) - raw(pre)).unsigned_abs();
+        if change == 0 {
+            return None;
+        }
+
+        Some(u64::try_from(change).unwrap_or(u64::MAX))
+    }
+}
+
+/// 交易中的一条已解析指令及其位置：顶层指令为 "<下标>"，CPI 指令为 "<所属顶层指令下标>.<下标>"
+#[derive(Debug, Clone, Copy)]
+pub struct PositionedInstruction<'a> {
+    pub top_level: usize,
+    pub inner: Option<usize>,
+    pub instruction: &'a ParsedInstruction,
+}
+
+impl PositionedInstruction<'_> {
+    pub fn position(&self) -> String {
+        match self.inner {
+            Some(inner) => format!("{}.{}", self.top_level, inner),
+            None => self.top_level.to_string(),
+        }
+    }
+}
+
+/// 收集交易中的全部已解析指令：先顶层指令，再 meta.inner_instructions 中的 CPI 指令。
+/// CPI 指令不会与顶层指令重复，内容相同的指令（如两笔相同的转账）按位置分别保留
+pub fn collect_parsed_instructions<'a>(
+    message: &'a UiParsedMessage,
+    meta: Option<&'a UiTransactionStatusMeta>,
+) -> Vec<PositionedInstruction<'a>> {
+    let parsed = |instruction: &'a UiInstruction| match instruction {
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed)) => Some(parsed),
+        _ => None,
+    };
+    let inner = meta
+        .and_then(|m| Option::from(m.inner_instructions.as_ref()))
+        .map(|inner: &Vec<_>| inner.as_slice())
+        .unwrap_or_default();
+
+    let top_level = message
+        .instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, instruction)| {
+            Some(PositionedInstruction {
+                top_level: index,
+                inner: None,
+                instruction: parsed(instruction)?,
+            })
+        });
+    let cpi = inner.iter().flat_map(|group| {
+        group
+            .instructions
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, instruction)| {
+                Some(PositionedInstruction {
+                    top_level: group.index as usize,
+                    inner: Some(index),
+                    instruction: parsed(instruction)?,
+                })
+            })
+    });
+    top_level.chain(cpi).collect()
+}
+
+/// 顶层指令在前，meta.inner_instructions 中的 CPI 指令在后
+fn all_instructions<'a>(
+    message: &'a UiParsedMessage,
+    meta: Option<&'a UiTransactionStatusMeta>,
+) -> impl Iterator<Item = &'a UiInstruction> {
+    let inner = meta
+        .and_then(|m| Option::from(m.inner_instructions.as_ref()))
+        .map(|inner: &Vec<_>| inner.as_slice())
+        .unwrap_or_default();
+
+    message
+        .instructions
+        .iter()
+        .chain(inner.iter().flat_map(|i| i.instructions.iter()))
+}
+
+/// 提取 SPL Memo（v1 / v2）指令中的备注文本，多条备注用 "; " 连接。
+/// 已解析的指令直接取 parsed 字符串，未解析的指令按 base58 解码后读取 UTF-8。
+pub fn parse_memo(
+    message: &UiParsedMessage,
+    meta: Option<&UiTransactionStatusMeta>,
+) -> Option<String> {
+    let memos: Vec<String> = all_instructions(message, meta)
+        .filter_map(|instruction| match instruction {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(ix))
+                if MEMO_PROGRAM_IDS.contains(&ix.program_id.as_str()) =>
+            {
+                ix.parsed.as_str().map(|s| s.to_string())
+            }
+            UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix))
+                if MEMO_PROGRAM_IDS.contains(&ix.program_id.as_str()) =>
+            {
+                let data = bs58::decode(&ix.data).into_vec().ok()?;
+                String::from_utf8(data).ok()
+            }
+            _ => None,
+        })
+        .collect();
+
+    if memos.is_empty() {
+        None
+    } else {
+        Some(memos.join("; "))
+    }
+}
+
+/// 顶层指令调用的程序 id，按出现顺序去重
+pub fn program_ids(message: &UiParsedMessage) -> Vec<String> {
+    let mut seen = HashSet::new();
+    message
+        .instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(ix)) => Some(ix.program_id.clone()),
+            UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) => {
+                Some(ix.program_id.clone())
+            }
+            UiInstruction::Compiled(ix) => message
+                .account_keys
+                .get(ix.program_id_index as usize)
+                .map(|k| k.pubkey.clone()),
+        })
+        .filter(|program_id| seen.insert(program_id.clone()))
+        .collect()
+}
+
+/// 从顶层指令中读取 SetComputeUnitLimit / SetComputeUnitPrice，没有 ComputeBudget 指令时返回 None。
+/// jsonParsed 编码不解析该程序，指令以 base58 原始数据形式给出。
+pub fn parse_compute_budget(message: &UiParsedMessage) -> Option<ComputeBudget> {
+    let mut budget = ComputeBudget::default();
+    let mut found = false;
+
+    for instruction in &message.instructions {
+        let UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) = instruction else {
+            budget.instruction_count += 1;
+            continue;
+        };
+        if ix.program_id != COMPUTE_BUDGET_PROGRAM_ID {
+            budget.instruction_count += 1;
+            continue;
+        }
+
+        found = true;
+        let Ok(data) = bs58::decode(&ix.data).into_vec() else {
+            continue;
+        };
+        match data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT, rest)) => {
+                budget.unit_limit = rest
+                    .get(..4)
+                    .and_then(|b| b.try_into().ok())
+                    .map(u32::from_le_bytes);
+            }
+            Some((&SET_COMPUTE_UNIT_PRICE, rest)) => {
+                budget.unit_price = rest
+                    .get(..8)
+                    .and_then(|b| b.try_into().ok())
+                    .map(u64::from_le_bytes);
+            }
+            _ => {}
+        }
+    }
+
+    found.then_some(budget)
+}
+
+/// 原始单位金额按精度换算为展示金额
+pub fn ui_amount(amount_raw: u64, decimals: u8) -> f64 {
+    amount_raw as f64 / 10f64.powi(decimals as i32)
+}
+
+/// 按金额和精度推断代币交易类型：精度为 0 且数量为 1 视为 NFT。
+/// 仅在无法查询 mint 信息时作为兜底，扫描器会按 mint 的供应量重新判断
+pub fn token_transaction_type(amount: f64, decimals: u64) -> TransactionType {
+    if decimals == 0 && (amount - 1.0).abs() < f64::EPSILON {
+        TransactionType::Nft
+    } else {
+        TransactionType::Token
+    }
+}
+
+/// 解析器支持的程序及解析内容，新增解析器时同步更新，/capabilities 以此为准
+pub fn supported_programs() -> Vec<ProgramCapability> {
+    let transfers = TRANSFER_PROGRAMS
+        .iter()
+        .map(|(name, program_id)| ProgramCapability::new(name, program_id, "transfer"));
+    let memos = MEMO_PROGRAM_IDS
+        .iter()
+        .map(|program_id| ProgramCapability::new("spl-memo", program_id, "memo"));
+    let compute_budget = std::iter::once(ProgramCapability::new(
+        "compute-budget",
+        COMPUTE_BUDGET_PROGRAM_ID,
+        "priority_fee",
+    ));
+    let accounts = TRANSFER_PROGRAMS
+        .iter()
+        .map(|(name, program_id)| ProgramCapability::new(name, program_id, "account"));
+    transfers
+        .chain(accounts)
+        .chain(memos)
+        .chain(compute_budget)
+        .collect()
+}
+
+/// 解析账户生命周期指令：system createAccount / createAccountWithSeed，
+/// spl-token initializeAccount(2/3) / closeAccount，其他指令返回 None。
+/// from 为出资方（关闭时为被关闭的账户），to 为新账户（关闭时为接收剩余 lamports 的账户）
+pub fn parse_account_event(instruction: &ParsedInstruction) -> Option<ParsedTransfer> {
+    let parsed = &instruction.parsed;
+    let instruction_type = parsed.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let info = parsed.get("info")?;
+    let field = |key: &str| {
+        info.get(key)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+
+    let (event, from, to, lamports, mint) = match instruction.program.as_str() {
+        "system"
+            if instruction_type == "createAccount"
+                || instruction_type == "createAccountWithSeed" =>
+        {
+            let lamports = info.get("lamports").and_then(|v| v.as_u64()).unwrap_or(0);
+            (
+                "create",
+                field("source")?,
+                field("newAccount")?,
+                lamports,
+                None,
+            )
+        }
+        "spl-token" | "spl-token-2022"
+            if matches!(
+                instruction_type,
+                "initializeAccount" | "initializeAccount2" | "initializeAccount3"
+            ) =>
+        {
+            (
+                "initialize",
+                field("owner")?,
+                field("account")?,
+                0,
+                field("mint"),
+            )
+        }
+        "spl-token" | "spl-token-2022" if instruction_type == "closeAccount" => {
+            ("close", field("account")?, field("destination")?, 0, None)
+        }
+        _ => return None,
+    };
+
+    let owner = field("owner").or_else(|| field("multisigOwner"));
+    Some(ParsedTransfer {
+        transaction_type: TransactionType::Account,
+        raw: serde_json::json!({
+            "event": event,
+            "program": instruction.program,
+            "owner": owner,
+            "instruction": parsed,
+        }),
+        from,
+        to: Some(to),
+        amount: ui_amount(lamports, SOL_DECIMALS),
+        amount_raw: lamports,
+        mint,
+        decimals: None,
+    })
+}
+
+/// 解析 system / spl-token 转账指令，其他指令返回 None。
+/// 代币转账优先使用交易前后余额确定 mint、精度和金额，余额缺失时退回指令中的数值。
+pub fn parse_transfer(
+    instruction: &ParsedInstruction,
+    balances: &TokenBalances,
+) -> Option<ParsedTransfer> {
+    let parsed = &instruction.parsed;
+    let instruction_type = parsed.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let info = parsed.get("info")?;
+
+    let from = info
+        .get("source")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let to = info
+        .get("destination")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    match instruction.program.as_str() {
+        "system" if instruction_type == "transfer" => {
+            let lamports = info.get("lamports").and_then(|v| v.as_u64()).unwrap_or(0);
+            Some(ParsedTransfer {
+                transaction_type: TransactionType::Native,
+                from,
+                to,
+                amount: ui_amount(lamports, SOL_DECIMALS),
+                amount_raw: lamports,
+                mint: None,
+                decimals: None,
+                raw: parsed.clone(),
+            })
+        }
+        "spl-token" | "spl-token-2022"
+            if instruction_type == "transfer" || instruction_type == "transferChecked" =>
+        {
+            let balance_info = to
+                .as_deref()
+                .and_then(|t| balances.mint_and_decimals(t))
+                .or_else(|| balances.mint_and_decimals(&from));
+            let mint = info
+                .get("mint")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .or_else(|| balance_info.as_ref().map(|(mint, _)| mint.clone()));
+            // 精度优先取交易内余额（plain transfer 指令本身不带精度），其次取指令中的值
+            let known_decimals = balance_info
+                .as_ref()
+                .map(|(_, decimals)| *decimals)
+                .or_else(|| {
+                    info.get("tokenAmount")
+                        .and_then(|t| t.get("decimals"))
+                        .or_else(|| info.get("decimals"))
+                        .and_then(|v| v.as_u64())
+                        .and_then(|d| u8::try_from(d).ok())
+                });
+            let decimals = known_decimals.unwrap_or(0);
+
+            // 金额取指令本身的值，余额只提供 mint 与精度；指令缺少金额时才退回余额变化
+            let amount_raw = instruction_raw_amount(info)
+                .or_else(|| to.as_deref().and_then(|t| balances.raw_delta(t)))
+                .or_else(|| balances.raw_delta(&from))
+                .unwrap_or(0);
+            let amount = ui_amount(amount_raw, decimals);
+            Some(ParsedTransfer {
+                transaction_type: token_transaction_type(amount, decimals as u64),
+                from,
+                to,
+                amount,
+                amount_raw,
+                mint,
+                decimals: known_decimals,
+                raw: parsed.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// 用记录中保存的 raw_data 重新解析，供解析逻辑修正后重放。
+/// 转账记录只保存了指令的 parsed 部分，程序按记录类型推断（代币转账两个 token 程序解析方式相同）；
+/// Unknown 记录没有保存指令，返回 None。重放时没有交易内余额，mint 与精度只能取自指令本身
+pub fn reparse_raw(
+    transaction_type: TransactionType,
+    raw: &serde_json::Value,
+) -> Option<ParsedTransfer> {
+    let (program, parsed) = match transaction_type {
+        TransactionType::Native => ("system", raw.clone()),
+        TransactionType::Token | TransactionType::Nft => ("spl-token", raw.clone()),
+        TransactionType::Account => (
+            raw.get("program")?.as_str()?,
+            raw.get("instruction")?.clone(),
+        ),
+        TransactionType::Unknown => return None,
+    };
+    let instruction = ParsedInstruction {
+        program: program.to_string(),
+        program_id: String::new(),
+        parsed,
+        stack_height: None,
+    };
+    parse_transfer(&instruction, &TokenBalances::default())
+        .or_else(|| parse_account_event(&instruction))
+}
+
+/// 从指令本身读取原始单位的金额：transferChecked 带 tokenAmount.amount，transfer 只有 amount
+fn instruction_raw_amount(info: &serde_json::Value) -> Option<u64> {
+    let amount = info
+        .get("tokenAmount")
+        .and_then(|t| t.get("amount"))
+        .or_else(|| info.get("amount"))?;
+    match amount {
+        serde_json::Value::String(s) => s.parse().ok(),
+        v => v.as_u64(),
+    }
+}
+
+/// spl-token 转账指令（transfer / transferChecked）的 info 字段
+fn token_transfer_info(instruction: &ParsedInstruction) -> Option<&serde_json::Value> {
+    if !matches!(instruction.program.as_str(), "spl-token" | "spl-token-2022") {
+        return None;
+    }
+    let instruction_type = instruction.parsed.get("type")?.as_str()?;
+    if instruction_type != "transfer" && instruction_type != "transferChecked" {
+        return None;
+    }
+    instruction.parsed.get("info")
+}
+
+#[cfg(test)]
+mod tests;
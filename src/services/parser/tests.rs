@@ -0,0 +1,424 @@
+use super::*;
+use serde_json::json;
+
+fn instruction(program: &str, parsed: serde_json::Value) -> ParsedInstruction {
+    serde_json::from_value(json!({
+        "program": program,
+        "programId": "11111111111111111111111111111111",
+        "parsed": parsed,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_parse_system_transfer() {
+    let ix = instruction(
+        "system",
+        json!({
+            "type": "transfer",
+            "info": {
+                "source": "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU",
+                "destination": "8yKZtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU",
+                "lamports": 1_500_000_000u64
+            }
+        }),
+    );
+
+    let transfer = parse_transfer(&ix, &TokenBalances::default()).unwrap();
+    assert!(matches!(transfer.transaction_type, TransactionType::Native));
+    assert_eq!(transfer.amount, 1.5);
+    assert_eq!(
+        transfer.to.as_deref(),
+        Some("8yKZtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU")
+    );
+}
+
+#[test]
+fn test_parse_ignores_non_transfer_instructions() {
+    let ix = instruction(
+        "system",
+        json!({ "type": "createAccount", "info": { "source": "a", "newAccount": "b" } }),
+    );
+
+    assert!(parse_transfer(&ix, &TokenBalances::default()).is_none());
+}
+
+#[test]
+fn test_parse_plain_token_transfer_uses_instruction_decimals_fallback() {
+    let ix = instruction(
+        "spl-token",
+        json!({
+            "type": "transferChecked",
+            "info": {
+                "source": "a",
+                "destination": "b",
+                "mint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "tokenAmount": { "amount": "2500000", "decimals": 6, "uiAmountString": "2.5" }
+            }
+        }),
+    );
+
+    let transfer = parse_transfer(&ix, &TokenBalances::default()).unwrap();
+    assert!(matches!(transfer.transaction_type, TransactionType::Token));
+    assert_eq!(transfer.amount, 2.5);
+}
+
+#[test]
+fn test_dust_thresholds_use_base_units() {
+    let ix = instruction(
+        "system",
+        json!({
+            "type": "transfer",
+            "info": { "source": "a", "destination": "b", "lamports": 5000u64 }
+        }),
+    );
+    let native = parse_transfer(&ix, &TokenBalances::default()).unwrap();
+    assert_eq!(native.base_units(), 5000);
+    assert!(!native.is_dust(0, 0));
+    assert!(native.is_dust(5001, 0));
+    assert!(!native.is_dust(5000, 0));
+
+    let ix = instruction(
+        "spl-token",
+        json!({
+            "type": "transferChecked",
+            "info": {
+                "source": "a",
+                "destination": "b",
+                "mint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "tokenAmount": { "amount": "2500000", "decimals": 6, "uiAmountString": "2.5" }
+            }
+        }),
+    );
+    let token = parse_transfer(&ix, &TokenBalances::default()).unwrap();
+    assert_eq!(token.base_units(), 2_500_000);
+    assert!(token.is_dust(0, 3_000_000));
+    assert!(!token.is_dust(3_000_000, 0));
+}
+
+fn message_with_instructions(instructions: serde_json::Value) -> UiParsedMessage {
+    serde_json::from_value(json!({
+        "accountKeys": [],
+        "recentBlockhash": "11111111111111111111111111111111",
+        "instructions": instructions,
+    }))
+    .unwrap()
+}
+
+fn compute_budget_instruction(data: &[u8]) -> serde_json::Value {
+    json!({
+        "programId": COMPUTE_BUDGET_PROGRAM_ID,
+        "accounts": [],
+        "data": bs58::encode(data).into_string(),
+    })
+}
+
+#[test]
+fn test_parse_compute_budget_priority_fee() {
+    let mut limit = vec![SET_COMPUTE_UNIT_LIMIT];
+    limit.extend_from_slice(&300_000u32.to_le_bytes());
+    let mut price = vec![SET_COMPUTE_UNIT_PRICE];
+    price.extend_from_slice(&50_000u64.to_le_bytes());
+    let message = message_with_instructions(json!([
+        compute_budget_instruction(&limit),
+        compute_budget_instruction(&price),
+    ]));
+
+    let budget = parse_compute_budget(&message).unwrap();
+    assert_eq!(budget.unit_limit, Some(300_000));
+    assert_eq!(budget.unit_price, Some(50_000));
+    // 50_000 micro-lamports × 300_000 CU = 15_000 lamports
+    assert_eq!(budget.priority_fee(), Some(0.000015));
+}
+
+#[test]
+fn test_parse_compute_budget_absent() {
+    let message = message_with_instructions(json!([]));
+    assert!(parse_compute_budget(&message).is_none());
+}
+
+#[test]
+fn test_parse_memo_parsed_and_raw() {
+    let message = message_with_instructions(json!([
+        {
+            "program": "spl-memo",
+            "programId": "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+            "parsed": "deposit-1024",
+        },
+        {
+            "programId": "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo",
+            "accounts": [],
+            "data": bs58::encode("order 42").into_string(),
+        },
+    ]));
+
+    assert_eq!(
+        parse_memo(&message, None).as_deref(),
+        Some("deposit-1024; order 42")
+    );
+}
+
+#[test]
+fn test_account_keys_include_lookup_table_addresses() {
+    let message: UiParsedMessage = serde_json::from_value(json!({
+        "accountKeys": [
+            { "pubkey": "Payer", "writable": true, "signer": true, "source": "transaction" },
+            { "pubkey": "Loaded1", "writable": true, "signer": false, "source": "lookupTable" },
+        ],
+        "recentBlockhash": "11111111111111111111111111111111",
+        "instructions": [],
+    }))
+    .unwrap();
+    let meta: UiTransactionStatusMeta = serde_json::from_value(json!({
+        "err": null,
+        "status": { "Ok": null },
+        "fee": 5000,
+        "preBalances": [],
+        "postBalances": [],
+        "loadedAddresses": { "writable": ["Loaded1"], "readonly": ["Loaded2"] },
+    }))
+    .unwrap();
+
+    assert_eq!(account_keys(&message, None), vec!["Payer", "Loaded1"]);
+    // 已并入 account_keys 的加载账户不重复，仅出现在 meta 中的账户追加在末尾
+    assert_eq!(
+        account_keys(&message, Some(&meta)),
+        vec!["Payer", "Loaded1", "Loaded2"]
+    );
+}
+
+#[test]
+fn test_supported_programs_cover_transfer_parsers() {
+    let programs = supported_programs();
+    for name in ["system", "spl-token", "spl-token-2022"] {
+        assert!(programs
+            .iter()
+            .any(|p| p.name == name && p.decodes == "transfer"));
+    }
+    assert!(programs
+        .iter()
+        .any(|p| p.program_id == COMPUTE_BUDGET_PROGRAM_ID));
+}
+
+#[test]
+fn test_parse_account_lifecycle_events() {
+    let create = instruction(
+        "system",
+        json!({
+            "type": "createAccount",
+            "info": { "source": "a", "newAccount": "b", "lamports": 2_039_280u64, "space": 165, "owner": "c" }
+        }),
+    );
+    let event = parse_account_event(&create).unwrap();
+    assert!(matches!(event.transaction_type, TransactionType::Account));
+    assert_eq!(event.from, "a");
+    assert_eq!(event.to.as_deref(), Some("b"));
+    assert_eq!(event.raw["event"], "create");
+    assert!(!event.is_dust(u64::MAX, u64::MAX));
+
+    let close = instruction(
+        "spl-token",
+        json!({
+            "type": "closeAccount",
+            "info": { "account": "d", "destination": "e", "owner": "f" }
+        }),
+    );
+    let event = parse_account_event(&close).unwrap();
+    assert_eq!(event.from, "d");
+    assert_eq!(event.to.as_deref(), Some("e"));
+    assert_eq!(event.raw["event"], "close");
+    assert_eq!(event.raw["owner"], "f");
+
+    let transfer = instruction(
+        "system",
+        json!({ "type": "transfer", "info": { "source": "a", "destination": "b", "lamports": 1 } }),
+    );
+    assert!(parse_account_event(&transfer).is_none());
+}
+
+#[test]
+fn test_token_amount_raw_keeps_full_precision() {
+    let ix = instruction(
+        "spl-token",
+        json!({
+            "type": "transferChecked",
+            "info": {
+                "source": "a",
+                "destination": "b",
+                "mint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "tokenAmount": { "amount": "12345678901234567891", "decimals": 18 }
+            }
+        }),
+    );
+
+    let transfer = parse_transfer(&ix, &TokenBalances::default()).unwrap();
+    // 超出 f64 精度的原始金额按整数保留
+    assert_eq!(transfer.amount_raw, 12_345_678_901_234_567_891);
+    assert_eq!(transfer.decimals, Some(18));
+    assert_eq!(transfer.amount, ui_amount(12_345_678_901_234_567_891, 18));
+}
+
+#[test]
+fn test_identical_transfers_are_kept_by_position() {
+    let transfer = json!({
+        "program": "system",
+        "programId": "11111111111111111111111111111111",
+        "parsed": {
+            "type": "transfer",
+            "info": { "source": "a", "destination": "b", "lamports": 1000u64 }
+        }
+    });
+    let message = message_with_instructions(json!([transfer, transfer]));
+
+    let instructions = collect_parsed_instructions(&message, None);
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[0].top_level, 0);
+    assert_eq!(instructions[1].top_level, 1);
+    assert!(instructions.iter().all(|ix| ix.inner.is_none()));
+}
+
+#[test]
+fn test_transfer_and_account_event_get_distinct_record_keys() {
+    let message = message_with_instructions(json!([
+        {
+            "program": "system",
+            "programId": "11111111111111111111111111111111",
+            "parsed": {
+                "type": "createAccount",
+                "info": { "source": "a", "newAccount": "b", "lamports": 2_039_280u64, "space": 0, "owner": "c" }
+            }
+        },
+        {
+            "program": "system",
+            "programId": "11111111111111111111111111111111",
+            "parsed": {
+                "type": "transfer",
+                "info": { "source": "a", "destination": "b", "lamports": 1000u64 }
+            }
+        }
+    ]));
+
+    let balances = TokenBalances::default();
+    let keys: Vec<String> = collect_parsed_instructions(&message, None)
+        .into_iter()
+        .filter_map(|positioned| {
+            let transfer = parse_transfer(positioned.instruction, &balances)
+                .or_else(|| parse_account_event(positioned.instruction))?;
+            let mut record = crate::models::tests::sample_transaction();
+            record.signature = "sig".to_string();
+            record.transaction_type = transfer.transaction_type;
+            record.set_position(&positioned.position());
+            Some(record.record_key)
+        })
+        .collect();
+
+    assert_eq!(keys, vec!["sig:0", "sig:1"]);
+}
+
+#[test]
+fn test_token_amount_comes_from_instruction_not_net_delta() {
+    let transfer = |amount: &str| {
+        json!({
+            "program": "spl-token",
+            "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "parsed": {
+                "type": "transfer",
+                "info": { "source": "Src", "destination": "Dst", "authority": "Owner", "amount": amount }
+            }
+        })
+    };
+    let message: UiParsedMessage = serde_json::from_value(json!({
+        "accountKeys": [
+            { "pubkey": "Owner", "writable": true, "signer": true, "source": "transaction" },
+            { "pubkey": "Src", "writable": true, "signer": false, "source": "transaction" },
+            { "pubkey": "Dst", "writable": true, "signer": false, "source": "transaction" },
+        ],
+        "recentBlockhash": "11111111111111111111111111111111",
+        "instructions": [transfer("100"), transfer("250")],
+    }))
+    .unwrap();
+    let balance = |index: u8, amount: &str| {
+        json!({
+            "accountIndex": index,
+            "mint": "Mint",
+            "uiTokenAmount": { "uiAmount": null, "decimals": 6, "amount": amount, "uiAmountString": "0" }
+        })
+    };
+    let meta: UiTransactionStatusMeta = serde_json::from_value(json!({
+        "err": null,
+        "status": { "Ok": null },
+        "fee": 5000,
+        "preBalances": [],
+        "postBalances": [],
+        "preTokenBalances": [balance(1, "1000"), balance(2, "0")],
+        "postTokenBalances": [balance(1, "650"), balance(2, "350")],
+    }))
+    .unwrap();
+
+    let balances = TokenBalances::new(&message, Some(&meta));
+    // 账户参与了两笔转账，余额变化不能代表单笔金额
+    assert_eq!(balances.raw_delta("Dst"), None);
+
+    let amounts: Vec<(u64, Option<u8>, Option<String>)> =
+        collect_parsed_instructions(&message, Some(&meta))
+            .into_iter()
+            .filter_map(|positioned| parse_transfer(positioned.instruction, &balances))
+            .map(|transfer| (transfer.amount_raw, transfer.decimals, transfer.mint))
+            .collect();
+    assert_eq!(
+        amounts,
+        vec![
+            (100, Some(6), Some("Mint".to_string())),
+            (250, Some(6), Some("Mint".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn test_watched_address_prefers_matching_side() {
+    let ix = instruction(
+        "system",
+        json!({ "type": "transfer", "info": { "source": "a", "destination": "b", "lamports": 1 } }),
+    );
+    let transfer = parse_transfer(&ix, &TokenBalances::default()).unwrap();
+
+    let receiver: HashSet<String> = ["b".to_string()].into();
+    assert_eq!(transfer.watched_address(&receiver).as_deref(), Some("b"));
+    let both: HashSet<String> = ["a".to_string(), "b".to_string()].into();
+    assert_eq!(transfer.watched_address(&both).as_deref(), Some("a"));
+}
+
+#[test]
+fn test_reparse_raw_replays_stored_instruction() {
+    let token = instruction(
+        "spl-token",
+        json!({
+            "type": "transferChecked",
+            "info": {
+                "source": "a",
+                "destination": "b",
+                "mint": "Mint",
+                "tokenAmount": { "amount": "2500000", "decimals": 6 }
+            }
+        }),
+    );
+    let transfer = parse_transfer(&token, &TokenBalances::default()).unwrap();
+    let replayed = reparse_raw(transfer.transaction_type, &transfer.raw).unwrap();
+    assert_eq!(replayed.amount_raw, 2_500_000);
+    assert_eq!(replayed.decimals, Some(6));
+    assert_eq!(replayed.mint.as_deref(), Some("Mint"));
+    assert_eq!(replayed.raw, transfer.raw);
+
+    let close = instruction(
+        "spl-token",
+        json!({
+            "type": "closeAccount",
+            "info": { "account": "d", "destination": "e", "owner": "f" }
+        }),
+    );
+    let event = parse_account_event(&close).unwrap();
+    let replayed = reparse_raw(TransactionType::Account, &event.raw).unwrap();
+    assert_eq!(replayed.raw, event.raw);
+
+    assert!(reparse_raw(TransactionType::Unknown, &json!({ "program_ids": [] })).is_none());
+}
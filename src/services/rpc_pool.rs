@@ -0,0 +1,109 @@
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// 所有 RPC 请求共享的令牌桶限流器，requests_per_second 为 0 时不限流
+#[derive(Clone)]
+pub struct RpcRateLimiter {
+    limiter: Option<Arc<DefaultDirectRateLimiter>>,
+}
+
+impl RpcRateLimiter {
+    pub fn new(requests_per_second: u32) -> Self {
+        let limiter = NonZeroU32::new(requests_per_second)
+            .map(|rate| Arc::new(RateLimiter::direct(Quota::per_second(rate))));
+        Self { limiter }
+    }
+
+    /// 等待直到可以发出下一个请求
+    pub async fn acquire(&self) {
+        if let Some(limiter) = &self.limiter {
+            limiter.until_ready().await;
+        }
+    }
+}
+
+pub struct RpcEndpoint {
+    pub url: String,
+    pub client: Arc<RpcClient>,
+    consecutive_failures: AtomicU32,
+}
+
+impl RpcEndpoint {
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+}
+
+/// 多个 RPC 端点组成的连接池，按连续失败次数选择最健康的端点
+pub struct RpcPool {
+    endpoints: Vec<RpcEndpoint>,
+    rate_limiter: RpcRateLimiter,
+}
+
+impl RpcPool {
+    pub fn new(
+        urls: &[String],
+        commitment: CommitmentConfig,
+        timeout: Duration,
+        rate_limiter: RpcRateLimiter,
+    ) -> Self {
+        let endpoints = urls
+            .iter()
+            .map(|url| RpcEndpoint {
+                url: url.clone(),
+                client: Arc::new(RpcClient::new_with_timeout_and_commitment(
+                    url.clone(),
+                    timeout,
+                    commitment,
+                )),
+                consecutive_failures: AtomicU32::new(0),
+            })
+            .collect();
+
+        Self {
+            endpoints,
+            rate_limiter,
+        }
+    }
+
+    pub fn rate_limiter(&self) -> &RpcRateLimiter {
+        &self.rate_limiter
+    }
+
+    /// 按健康度排序的端点下标，连续失败次数相同时保持配置顺序
+    pub fn ranked(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+        order.sort_by_key(|&i| self.endpoints[i].consecutive_failures());
+        order
+    }
+
+    pub fn endpoint(&self, index: usize) -> &RpcEndpoint {
+        &self.endpoints[index]
+    }
+
+    pub fn record_success(&self, index: usize) {
+        self.endpoints[index]
+            .consecutive_failures
+            .store(0, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self, index: usize) {
+        let endpoint = &self.endpoints[index];
+        let failures = endpoint
+            .consecutive_failures
+            .fetch_add(1, Ordering::Relaxed)
+            + 1;
+        if failures == 1 || failures.is_multiple_of(10) {
+            warn!(
+                "RPC endpoint {} has failed {} times in a row",
+                endpoint.url, failures
+            );
+        }
+    }
+}
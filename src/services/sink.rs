@@ -0,0 +1,173 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::models::Transaction;
+use crate::services::websocket::WebSocketManager;
+use crate::utils::kafka::KafkaProducer;
+use crate::utils::retry::backoff_delay;
+
+/// 交易输出，扫描器把每笔入库的交易依次投递给所有已配置的输出
+#[async_trait]
+pub trait TransactionSink: Send + Sync {
+    /// 输出名称，用于日志
+    fn name(&self) -> &str;
+
+    async fn emit(&self, tx: &Transaction) -> Result<()>;
+
+    /// 投递失败时写入死信集合使用的 topic，返回 None 表示失败的交易不需要重放
+    fn dead_letter_topic(&self, _tx: &Transaction) -> Option<String> {
+        None
+    }
+}
+
+/// 同一个输出还需要在别处使用时（如 Kafka producer 同时投递事件），以 Arc 共享
+#[async_trait]
+impl<T: TransactionSink + ?Sized> TransactionSink for Arc<T> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    async fn emit(&self, tx: &Transaction) -> Result<()> {
+        (**self).emit(tx).await
+    }
+
+    fn dead_letter_topic(&self, tx: &Transaction) -> Option<String> {
+        (**self).dead_letter_topic(tx)
+    }
+}
+
+#[async_trait]
+impl TransactionSink for KafkaProducer {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn emit(&self, tx: &Transaction) -> Result<()> {
+        self.send_transaction(tx).await
+    }
+
+    fn dead_letter_topic(&self, tx: &Transaction) -> Option<String> {
+        Some(self.topic_for(tx).to_string())
+    }
+}
+
+/// 推送给订阅了相关地址的 WebSocket 连接
+pub struct WebSocketSink {
+    ws_manager: Arc<RwLock<WebSocketManager>>,
+}
+
+impl WebSocketSink {
+    pub fn new(ws_manager: Arc<RwLock<WebSocketManager>>) -> Self {
+        Self { ws_manager }
+    }
+}
+
+#[async_trait]
+impl TransactionSink for WebSocketSink {
+    fn name(&self) -> &str {
+        "websocket"
+    }
+
+    async fn emit(&self, tx: &Transaction) -> Result<()> {
+        self.ws_manager.read().await.broadcast_transaction(tx).await;
+        Ok(())
+    }
+}
+
+/// 以 JSON POST 每笔交易到 webhook；配置了密钥时在 X-Signature 头中附带请求体的
+/// HMAC-SHA256 签名，格式为 sha256=<hex>
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    secret: Option<String>,
+    max_retries: u32,
+    retry_base_delay_ms: u64,
+}
+
+impl WebhookSink {
+    pub fn new(
+        url: String,
+        secret: Option<String>,
+        timeout: Duration,
+        max_retries: u32,
+        retry_base_delay_ms: u64,
+    ) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Self {
+            client,
+            url,
+            secret,
+            max_retries,
+            retry_base_delay_ms,
+        })
+    }
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+}
+
+#[async_trait]
+impl TransactionSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn emit(&self, tx: &Transaction) -> Result<()> {
+        let body = serde_json::to_vec(tx)?;
+        let signature = self
+            .secret
+            .as_deref()
+            .map(|secret| Self::sign(secret, &body));
+
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header("X-Signature", signature);
+            }
+
+            // 网络错误、429 和 5xx 重试，其他状态码直接失败
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let error = anyhow!("Webhook responded with {}", status);
+                    if status.as_u16() != 429 && !status.is_server_error() {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(e) => e.into(),
+            };
+            if attempt >= self.max_retries {
+                return Err(error);
+            }
+
+            let delay = backoff_delay(self.retry_base_delay_ms, attempt);
+            warn!(
+                "Webhook delivery of {} failed (attempt {}): {}, retrying in {:?}",
+                tx.signature,
+                attempt + 1,
+                error,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use lru::LruCache;
+use solana_client::client_error::ClientError;
+use solana_sdk::pubkey::Pubkey;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::services::rpc_pool::RpcPool;
+
+// Metaplex Token Metadata 程序
+const METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+// SPL Mint 账户中 supply 字段的偏移：mint_authority(4 + 32)
+const MINT_SUPPLY_OFFSET: usize = 36;
+// SPL Mint 账户中 decimals 字段的偏移：mint_authority(4 + 32) + supply(8)
+const MINT_DECIMALS_OFFSET: usize = 44;
+// Metadata 账户中 name 字段的偏移：key(1) + update_authority(32) + mint(32)
+const METADATA_NAME_OFFSET: usize = 65;
+// 缓存的 mint 数量上限，超出后淘汰最久未使用的
+const CACHE_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenMetadata {
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub supply: Option<u64>,
+}
+
+impl TokenMetadata {
+    /// 供应量为 1 且精度为 0 的 mint 视为 NFT；mint 信息缺失时返回 None
+    pub fn is_nft(&self) -> Option<bool> {
+        match (self.supply, self.decimals) {
+            (Some(supply), Some(decimals)) => Some(supply == 1 && decimals == 0),
+            _ => None,
+        }
+    }
+}
+
+/// 根据 mint 地址解析代币符号与精度，结果在内存中按 TTL 缓存（LRU，容量有限）
+pub struct TokenMetadataService {
+    rpc_pool: Arc<RpcPool>,
+    cache: Mutex<LruCache<String, (TokenMetadata, Instant)>>,
+    ttl: Duration,
+}
+
+impl TokenMetadataService {
+    pub fn new(rpc_pool: Arc<RpcPool>, ttl_secs: u64) -> Self {
+        Self {
+            rpc_pool,
+            cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(CACHE_CAPACITY).unwrap_or(NonZeroUsize::MIN),
+            )),
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    pub async fn get_metadata(&self, mint: &str) -> TokenMetadata {
+        if let Some((metadata, fetched_at)) = self.cache.lock().unwrap().get(mint) {
+            if fetched_at.elapsed() < self.ttl {
+                return metadata.clone();
+            }
+        }
+
+        // 账户不存在是确定的结果，可以缓存；RPC 失败不缓存，下次遇到该 mint 时重新查询
+        match self.fetch_metadata(mint).await {
+            Ok(metadata) => {
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .put(mint.to_string(), (metadata.clone(), Instant::now()));
+                metadata
+            }
+            Err(e) => {
+                debug!("Failed to fetch metadata for mint {}: {}", mint, e);
+                TokenMetadata::default()
+            }
+        }
+    }
+
+    async fn fetch_metadata(&self, mint: &str) -> Result<TokenMetadata, ClientError> {
+        let Ok(mint_pubkey) = Pubkey::from_str(mint) else {
+            return Ok(TokenMetadata::default());
+        };
+
+        let (supply, decimals) = match self.fetch_account_data(&mint_pubkey).await? {
+            Some(data) => (
+                data.get(MINT_SUPPLY_OFFSET..MINT_DECIMALS_OFFSET)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(u64::from_le_bytes),
+                data.get(MINT_DECIMALS_OFFSET).copied(),
+            ),
+            None => {
+                debug!("Mint account {} not found", mint);
+                (None, None)
+            }
+        };
+
+        let program_id = Pubkey::from_str(METADATA_PROGRAM_ID).expect("valid program id");
+        let (metadata_pda, _) = Pubkey::find_program_address(
+            &[b"metadata", program_id.as_ref(), mint_pubkey.as_ref()],
+            &program_id,
+        );
+        let symbol = match self.fetch_account_data(&metadata_pda).await? {
+            Some(data) => parse_metadata_symbol(&data),
+            None => {
+                debug!("No Metaplex metadata for mint {}", mint);
+                None
+            }
+        };
+
+        Ok(TokenMetadata {
+            symbol,
+            decimals,
+            supply,
+        })
+    }
+
+    /// 按健康度依次尝试连接池中的端点读取账户数据，账户不存在时返回 None，全部端点失败时返回最后一个错误
+    async fn fetch_account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        let mut last_error = None;
+        for index in self.rpc_pool.ranked() {
+            let client = self.rpc_pool.endpoint(index).client.clone();
+            self.rpc_pool.rate_limiter().acquire().await;
+            match client
+                .get_account_with_commitment(pubkey, client.commitment())
+                .await
+            {
+                Ok(response) => {
+                    self.rpc_pool.record_success(index);
+                    return Ok(response.value.map(|account| account.data));
+                }
+                Err(e) => {
+                    self.rpc_pool.record_failure(index);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("RPC pool has at least one endpoint"))
+    }
+}
+
+/// 从 Metaplex Metadata 账户数据中读取 symbol（borsh 编码，name 之后紧跟 symbol）
+fn parse_metadata_symbol(data: &[u8]) -> Option<String> {
+    let (_, rest) = read_borsh_string(data.get(METADATA_NAME_OFFSET..)?)?;
+    let (symbol, _) = read_borsh_string(rest)?;
+    let symbol = symbol.trim_end_matches('\0').trim().to_string();
+    if symbol.is_empty() {
+        None
+    } else {
+        Some(symbol)
+    }
+}
+
+fn read_borsh_string(data: &[u8]) -> Option<(String, &[u8])> {
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let bytes = data.get(4..4 + len)?;
+    Some((
+        String::from_utf8_lossy(bytes).into_owned(),
+        &data[4 + len..],
+    ))
+}
@@ -1,51 +1,199 @@
 use axum::extract::ws::Message;
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
-use tokio::sync::{mpsc::UnboundedSender, RwLock};
-use tracing::info;
+use mongodb::Database;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{error::TrySendError, Sender};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::db::TransactionRepo;
+use crate::models::{Transaction, TransactionQuery, TransactionType};
+use crate::utils::metrics;
+
+/// 订阅时推送历史快照的最大条数
+const MAX_SNAPSHOT_LIMIT: u32 = 100;
+
+/// 每个连接记住的最近推送过的记录键数
+const RECENT_RECORD_KEYS_CAPACITY: usize = 256;
 
 pub struct WebSocketManager {
+    db: Database,
     connections: Arc<RwLock<HashMap<String, WebSocketConnection>>>,
     address_subscribers: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    /// 按代币 mint 订阅的连接
+    mint_subscribers: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    /// 订阅全部交易的连接
+    broadcast_all: Arc<RwLock<HashSet<String>>>,
+    /// 订阅扫描进度的连接
+    status_subscribers: Arc<RwLock<HashSet<String>>>,
+    max_connections: usize,
+    channel_capacity: usize,
 }
 
 pub struct WebSocketConnection {
     pub id: String,
-    pub subscribed_addresses: HashMap<String, bool>,
-    pub sender: UnboundedSender<Message>,
+    /// 订阅的地址及其交易类型过滤，空列表表示全部类型
+    pub subscribed_addresses: HashMap<String, Vec<TransactionType>>,
+    /// 订阅的代币 mint 及其交易类型过滤
+    pub subscribed_mints: HashMap<String, Vec<TransactionType>>,
+    /// subscribe_all 的交易类型过滤，None 表示未订阅全部
+    pub all_types: Option<Vec<TransactionType>>,
+    pub sender: Sender<Message>,
+    /// 取消后连接处理任务会主动断开客户端
+    pub cancel: CancellationToken,
+    /// 最近推送过的记录键，分叉重扫等情况下同一条记录再次广播时不重复推送；
+    /// 同一签名下的多条记录各自推送
+    recent_record_keys: Mutex<RecentRecordKeys>,
+}
+
+/// 固定容量的记录键环形缓冲，满了之后淘汰最早的记录键
+#[derive(Default)]
+struct RecentRecordKeys {
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+}
+
+impl RecentRecordKeys {
+    /// 登记记录键，已存在时返回 false
+    fn insert(&mut self, record_key: &str) -> bool {
+        if self.seen.contains(record_key) {
+            return false;
+        }
+        if self.order.len() >= RECENT_RECORD_KEYS_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(record_key.to_string());
+        self.seen.insert(record_key.to_string());
+        true
+    }
+}
+
+impl WebSocketConnection {
+    fn accepts(&self, transaction: &Transaction) -> bool {
+        let tx_type = &transaction.transaction_type;
+        if let Some(types) = &self.all_types {
+            if type_matches(types, tx_type) {
+                return true;
+            }
+        }
+        let mint_matches = transaction
+            .token_mint
+            .as_ref()
+            .and_then(|mint| self.subscribed_mints.get(mint))
+            .is_some_and(|types| type_matches(types, tx_type));
+        mint_matches
+            || std::iter::once(Some(&transaction.from_address))
+                .chain(std::iter::once(transaction.to_address.as_ref()))
+                .flatten()
+                .filter_map(|address| self.subscribed_addresses.get(address))
+                .any(|types| type_matches(types, tx_type))
+    }
+
+    /// 该记录是否第一次推送给此连接
+    fn mark_sent(&self, record_key: &str) -> bool {
+        self.recent_record_keys.lock().unwrap().insert(record_key)
+    }
+}
+
+/// 从订阅索引中移除连接，集合为空时删除该键
+fn remove_subscriber(index: &mut HashMap<String, HashSet<String>>, key: &str, connection_id: &str) {
+    if let Some(set) = index.get_mut(key) {
+        set.remove(connection_id);
+        if set.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+fn type_matches(filter: &[TransactionType], tx_type: &TransactionType) -> bool {
+    filter.is_empty() || filter.contains(tx_type)
+}
+
+/// 非阻塞投递，发送队列已满时返回 false
+fn try_deliver(connection: &WebSocketConnection, message: Message) -> bool {
+    !matches!(
+        connection.sender.try_send(message),
+        Err(TrySendError::Full(_))
+    )
 }
 
 impl WebSocketManager {
-    pub fn new() -> Self {
+    pub fn new(db: Database, max_connections: usize, channel_capacity: usize) -> Self {
         Self {
+            db,
             connections: Arc::new(RwLock::new(HashMap::new())),
             address_subscribers: Arc::new(RwLock::new(HashMap::new())),
+            mint_subscribers: Arc::new(RwLock::new(HashMap::new())),
+            broadcast_all: Arc::new(RwLock::new(HashSet::new())),
+            status_subscribers: Arc::new(RwLock::new(HashSet::new())),
+            max_connections,
+            channel_capacity,
         }
     }
 
-    pub async fn add_connection(&self, connection_id: String, sender: UnboundedSender<Message>) {
+    /// 每个连接发送队列的容量
+    pub fn channel_capacity(&self) -> usize {
+        self.channel_capacity
+    }
+
+    /// 当前连接数
+    pub async fn connection_count(&self) -> usize {
+        self.connections.read().await.len()
+    }
+
+    pub async fn is_full(&self) -> bool {
+        self.connections.read().await.len() >= self.max_connections
+    }
+
+    pub async fn add_connection(
+        &self,
+        connection_id: String,
+        sender: Sender<Message>,
+        cancel: CancellationToken,
+    ) -> Result<(), String> {
+        let mut connections = self.connections.write().await;
+        if connections.len() >= self.max_connections {
+            return Err(format!(
+                "Connection limit of {} reached",
+                self.max_connections
+            ));
+        }
         let connection = WebSocketConnection {
             id: connection_id.clone(),
             subscribed_addresses: HashMap::new(),
+            subscribed_mints: HashMap::new(),
+            all_types: None,
             sender,
+            cancel,
+            recent_record_keys: Mutex::new(RecentRecordKeys::default()),
         };
-        let mut connections = self.connections.write().await;
         connections.insert(connection_id.clone(), connection);
+        metrics::set_websocket_connections(connections.len());
         info!("Added WebSocket connection: {}", connection_id);
+        Ok(())
     }
 
     pub async fn remove_connection(&self, connection_id: &str) {
         let mut connections = self.connections.write().await;
-        if let Some(conn) = connections.remove(connection_id) {
+        let removed = connections.remove(connection_id);
+        metrics::set_websocket_connections(connections.len());
+        if let Some(conn) = removed {
+            conn.cancel.cancel();
             let mut index = self.address_subscribers.write().await;
             for address in conn.subscribed_addresses.keys() {
-                if let Some(set) = index.get_mut(address) {
-                    set.remove(connection_id);
-                    if set.is_empty() {
-                        index.remove(address);
-                    }
-                }
+                remove_subscriber(&mut index, address, connection_id);
             }
+            drop(index);
+            let mut index = self.mint_subscribers.write().await;
+            for mint in conn.subscribed_mints.keys() {
+                remove_subscriber(&mut index, mint, connection_id);
+            }
+            drop(index);
+            self.broadcast_all.write().await.remove(connection_id);
+            self.status_subscribers.write().await.remove(connection_id);
         }
         info!("Removed WebSocket connection: {}", connection_id);
     }
@@ -54,13 +202,16 @@ impl WebSocketManager {
         &self,
         connection_id: &str,
         address: String,
+        types: Vec<TransactionType>,
+        snapshot_limit: Option<u32>,
     ) -> Result<(), String> {
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(connection_id) {
             let addr = address.clone();
+            // 重复订阅时覆盖原有的类型过滤
             connection
                 .subscribed_addresses
-                .insert(address.clone(), true);
+                .insert(address.clone(), types.clone());
             drop(connections);
             let mut index = self.address_subscribers.write().await;
             index
@@ -71,12 +222,60 @@ impl WebSocketManager {
                 "Connection {} subscribed to address {}",
                 connection_id, addr
             );
+            if let Some(limit) = snapshot_limit.filter(|limit| *limit > 0) {
+                self.send_snapshot(connection_id, &addr, &types, limit)
+                    .await?;
+            }
             Ok(())
         } else {
             Err("Connection not found".to_string())
         }
     }
 
+    /// 推送该地址最近的历史交易，便于客户端连接后直接渲染
+    async fn send_snapshot(
+        &self,
+        connection_id: &str,
+        address: &str,
+        types: &[TransactionType],
+        limit: u32,
+    ) -> Result<(), String> {
+        // 类型过滤放进查询，limit 作用于过滤后的结果
+        let query = TransactionQuery {
+            address: Some(address.to_string()),
+            transaction_types: types.to_vec(),
+            ..Default::default()
+        };
+        let page = TransactionRepo::new(self.db.clone())
+            .get_transactions_cursor(&query, None, limit.min(MAX_SNAPSHOT_LIMIT))
+            .await
+            .map_err(|e| format!("Failed to load snapshot: {}", e))?;
+        let transactions: Vec<Transaction> = page.data;
+
+        let snapshot = serde_json::json!({
+            "type": "snapshot",
+            "address": address,
+            "transactions": transactions,
+        });
+        let connections = self.connections.read().await;
+        let delivered = match connections.get(connection_id) {
+            Some(conn) => {
+                // 快照中的交易之后再被广播时不重复推送
+                for tx in &transactions {
+                    conn.mark_sent(&tx.record_key);
+                }
+                try_deliver(conn, Message::Text(snapshot.to_string()))
+            }
+            None => true,
+        };
+        drop(connections);
+        if !delivered {
+            self.drop_slow_connections(vec![connection_id.to_string()])
+                .await;
+        }
+        Ok(())
+    }
+
     pub async fn unsubscribe_from_address(
         &self,
         connection_id: &str,
@@ -87,12 +286,7 @@ impl WebSocketManager {
             connection.subscribed_addresses.remove(address);
             drop(connections);
             let mut index = self.address_subscribers.write().await;
-            if let Some(set) = index.get_mut(address) {
-                set.remove(connection_id);
-                if set.is_empty() {
-                    index.remove(address);
-                }
-            }
+            remove_subscriber(&mut index, address, connection_id);
             info!(
                 "Connection {} unsubscribed from address {}",
                 connection_id, address
@@ -103,7 +297,143 @@ impl WebSocketManager {
         }
     }
 
-    pub async fn broadcast_transaction(&self, transaction: &crate::models::Transaction) {
+    /// 订阅某个代币 mint 的全部交易，重复订阅时覆盖原有的类型过滤
+    pub async fn subscribe_mint(
+        &self,
+        connection_id: &str,
+        mint: String,
+        types: Vec<TransactionType>,
+    ) -> Result<(), String> {
+        let mut connections = self.connections.write().await;
+        match connections.get_mut(connection_id) {
+            Some(connection) => {
+                connection.subscribed_mints.insert(mint.clone(), types);
+            }
+            None => return Err("Connection not found".to_string()),
+        }
+        drop(connections);
+        self.mint_subscribers
+            .write()
+            .await
+            .entry(mint.clone())
+            .or_default()
+            .insert(connection_id.to_string());
+        info!("Connection {} subscribed to mint {}", connection_id, mint);
+        Ok(())
+    }
+
+    pub async fn unsubscribe_mint(&self, connection_id: &str, mint: &str) -> Result<(), String> {
+        let mut connections = self.connections.write().await;
+        match connections.get_mut(connection_id) {
+            Some(connection) => {
+                connection.subscribed_mints.remove(mint);
+            }
+            None => return Err("Connection not found".to_string()),
+        }
+        drop(connections);
+        let mut index = self.mint_subscribers.write().await;
+        remove_subscriber(&mut index, mint, connection_id);
+        info!(
+            "Connection {} unsubscribed from mint {}",
+            connection_id, mint
+        );
+        Ok(())
+    }
+
+    pub async fn subscribe_all(
+        &self,
+        connection_id: &str,
+        types: Vec<TransactionType>,
+    ) -> Result<(), String> {
+        let mut connections = self.connections.write().await;
+        match connections.get_mut(connection_id) {
+            Some(connection) => connection.all_types = Some(types),
+            None => return Err("Connection not found".to_string()),
+        }
+        drop(connections);
+        self.broadcast_all
+            .write()
+            .await
+            .insert(connection_id.to_string());
+        info!(
+            "Connection {} subscribed to all transactions",
+            connection_id
+        );
+        Ok(())
+    }
+
+    /// 一次性取消全部交易订阅（全量、地址、mint），扫描进度订阅不受影响，完成后回复 unsubscribed_all
+    pub async fn unsubscribe_all(&self, connection_id: &str) -> Result<(), String> {
+        let mut connections = self.connections.write().await;
+        let Some(connection) = connections.get_mut(connection_id) else {
+            return Err("Connection not found".to_string());
+        };
+        connection.all_types = None;
+        let addresses: Vec<String> = connection
+            .subscribed_addresses
+            .drain()
+            .map(|(k, _)| k)
+            .collect();
+        let mints: Vec<String> = connection
+            .subscribed_mints
+            .drain()
+            .map(|(k, _)| k)
+            .collect();
+        drop(connections);
+
+        self.broadcast_all.write().await.remove(connection_id);
+        let mut index = self.address_subscribers.write().await;
+        for address in &addresses {
+            remove_subscriber(&mut index, address, connection_id);
+        }
+        drop(index);
+        let mut index = self.mint_subscribers.write().await;
+        for mint in &mints {
+            remove_subscriber(&mut index, mint, connection_id);
+        }
+        drop(index);
+        info!(
+            "Connection {} unsubscribed from all transactions ({} addresses, {} mints)",
+            connection_id,
+            addresses.len(),
+            mints.len()
+        );
+
+        let confirmation = serde_json::json!({ "type": "unsubscribed_all" });
+        let connections = self.connections.read().await;
+        let delivered = connections
+            .get(connection_id)
+            .is_none_or(|conn| try_deliver(conn, Message::Text(confirmation.to_string())));
+        drop(connections);
+        if !delivered {
+            self.drop_slow_connections(vec![connection_id.to_string()])
+                .await;
+        }
+        Ok(())
+    }
+
+    pub async fn subscribe_status(&self, connection_id: &str) -> Result<(), String> {
+        if !self.connections.read().await.contains_key(connection_id) {
+            return Err("Connection not found".to_string());
+        }
+        self.status_subscribers
+            .write()
+            .await
+            .insert(connection_id.to_string());
+        info!("Connection {} subscribed to scan status", connection_id);
+        Ok(())
+    }
+
+    pub async fn unsubscribe_status(&self, connection_id: &str) -> Result<(), String> {
+        if !self.connections.read().await.contains_key(connection_id) {
+            return Err("Connection not found".to_string());
+        }
+        self.status_subscribers.write().await.remove(connection_id);
+        info!("Connection {} unsubscribed from scan status", connection_id);
+        Ok(())
+    }
+
+    pub async fn broadcast_transaction(&self, transaction: &Transaction) {
         let payload = serde_json::to_string(transaction).unwrap_or_else(|_| "{}".to_string());
         let mut targets: HashSet<String> = HashSet::new();
         let index = self.address_subscribers.read().await;
@@ -116,16 +446,92 @@ impl WebSocketManager {
             }
         }
         drop(index);
+        if let Some(mint) = transaction.token_mint.as_ref() {
+            if let Some(set) = self.mint_subscribers.read().await.get(mint) {
+                targets.extend(set.iter().cloned());
+            }
+        }
+        targets.extend(self.broadcast_all.read().await.iter().cloned());
         let connections = self.connections.read().await;
+        let mut slow = Vec::new();
         for cid in targets {
-            if let Some(conn) = connections.get(&cid) {
-                let _ = conn.sender.send(Message::Text(payload.clone()));
+            if let Some(conn) = connections
+                .get(&cid)
+                .filter(|conn| conn.accepts(transaction))
+                .filter(|conn| conn.mark_sent(&transaction.record_key))
+            {
+                if !try_deliver(conn, Message::Text(payload.clone())) {
+                    slow.push(cid);
+                }
             }
         }
+        drop(connections);
+        self.drop_slow_connections(slow).await;
+    }
+
+    /// 向所有连接广播事件消息
+    pub async fn broadcast_event(&self, event: &serde_json::Value) {
+        let payload = event.to_string();
+        let connections = self.connections.read().await;
+        let slow: Vec<String> = connections
+            .values()
+            .filter(|conn| !try_deliver(conn, Message::Text(payload.clone())))
+            .map(|conn| conn.id.clone())
+            .collect();
+        drop(connections);
+        self.drop_slow_connections(slow).await;
+    }
+
+    /// 向订阅了扫描进度的连接推送状态消息
+    pub async fn broadcast_status(&self, status: &serde_json::Value) {
+        let payload = status.to_string();
+        let subscribers = self.status_subscribers.read().await;
+        let connections = self.connections.read().await;
+        let slow: Vec<String> = subscribers
+            .iter()
+            .filter_map(|cid| connections.get(cid))
+            .filter(|conn| !try_deliver(conn, Message::Text(payload.clone())))
+            .map(|conn| conn.id.clone())
+            .collect();
+        drop(connections);
+        drop(subscribers);
+        self.drop_slow_connections(slow).await;
+    }
+
+    /// 发送队列已满说明客户端消费跟不上，直接断开，避免消息无限堆积
+    async fn drop_slow_connections(&self, connection_ids: Vec<String>) {
+        for connection_id in connection_ids {
+            warn!(
+                "Dropping slow WebSocket connection {}: send queue is full",
+                connection_id
+            );
+            metrics::record_websocket_slow_client_dropped();
+            self.remove_connection(&connection_id).await;
+        }
+    }
+
+    /// 向所有连接发送关闭帧并清空连接，服务退出前调用
+    pub async fn close_all_connections(&self) {
+        let mut connections = self.connections.write().await;
+        for conn in connections.values() {
+            let _ = conn.sender.try_send(Message::Close(None));
+        }
+        info!("Closing {} WebSocket connections", connections.len());
+        connections.clear();
+        metrics::set_websocket_connections(0);
+        self.address_subscribers.write().await.clear();
+        self.mint_subscribers.write().await.clear();
+        self.broadcast_all.write().await.clear();
+        self.status_subscribers.write().await.clear();
     }
 
     pub async fn get_subscribed_addresses(&self) -> Vec<String> {
         let index = self.address_subscribers.read().await;
         index.keys().cloned().collect()
     }
+
+    /// 至少有一个连接订阅的地址数
+    pub async fn subscribed_address_count(&self) -> usize {
+        self.address_subscribers.read().await.len()
+    }
 }
@@ -1,5 +1,11 @@
 use anyhow::Result;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
 use thiserror::Error;
+use tracing::error;
+
+use crate::models::RpcResponse;
 
 #[derive(Error, Debug)]
 pub enum ScannerError {
@@ -24,9 +30,27 @@ pub enum ScannerError {
     #[error("Transaction not found: {0}")]
     TransactionNotFound(String),
 
+    #[error("Address not found: {0}")]
+    AddressNotFound(String),
+
+    #[error("Slot {0} was skipped by the leader")]
+    SlotSkipped(u64),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    #[error("Unauthorized")]
+    Unauthorized,
+
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
@@ -49,4 +73,41 @@ impl From<rdkafka::error::KafkaError> for ScannerError {
     }
 }
 
+// 业务代码统一返回 anyhow::Error，能还原为 ScannerError 的保留原始类型，其余视为内部错误
+impl From<anyhow::Error> for ScannerError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<ScannerError>() {
+            Ok(scanner_error) => scanner_error,
+            Err(error) => ScannerError::InternalError(error.to_string()),
+        }
+    }
+}
+
+impl ScannerError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ScannerError::InvalidAddress(_) | ScannerError::InvalidRequest(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            ScannerError::TransactionNotFound(_) | ScannerError::AddressNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            ScannerError::Conflict(_) | ScannerError::LimitExceeded(_) => StatusCode::CONFLICT,
+            ScannerError::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            ScannerError::Unauthorized => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ScannerError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("Request failed: {}", self);
+        }
+        (status, Json(RpcResponse::<()>::error(self.to_string()))).into_response()
+    }
+}
+
 pub type ScannerResult<T> = Result<T, ScannerError>;
@@ -1,16 +1,28 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rdkafka::config::ClientConfig;
-use rdkafka::error::{KafkaError, RDKafkaErrorCode};
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use serde::Serialize;
 use serde_json;
-use tracing::{error, info};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
-use crate::config::KafkaConfig;
-use crate::models::Transaction;
+use crate::config::{KafkaConfig, KafkaKeyStrategy};
+use crate::models::{AddressEvent, CircuitState, Transaction, TransactionType};
+use crate::utils::metrics;
 
 pub struct KafkaProducer {
     producer: FutureProducer,
     transaction_topic: String,
+    native_topic: Option<String>,
+    token_topic: Option<String>,
+    nft_topic: Option<String>,
+    event_topic: String,
+    address_events_topic: String,
+    key_strategy: KafkaKeyStrategy,
+    send_timeout: Duration,
+    breaker: CircuitBreaker,
 }
 
 impl KafkaProducer {
@@ -18,55 +30,190 @@ impl KafkaProducer {
         let producer: FutureProducer = ClientConfig::new()
             .set("bootstrap.servers", &config.brokers)
             .set("client.id", &config.client_id)
-            .set("message.timeout.ms", "5000")
+            .set("message.timeout.ms", config.send_timeout_ms.to_string())
             .set("request.required.acks", "1")
             .create()?;
 
         Ok(Self {
             producer,
             transaction_topic: config.transaction_topic.clone(),
+            native_topic: config.native_topic.clone(),
+            token_topic: config.token_topic.clone(),
+            nft_topic: config.nft_topic.clone(),
+            event_topic: config.event_topic.clone(),
+            address_events_topic: config.address_events_topic.clone(),
+            key_strategy: config.key_strategy,
+            send_timeout: Duration::from_millis(config.send_timeout_ms),
+            breaker: CircuitBreaker::new(
+                config.circuit_failure_threshold,
+                Duration::from_secs(config.circuit_cooldown_secs),
+            ),
         })
     }
 
     pub async fn send_transaction(&self, transaction: &Transaction) -> Result<()> {
         let message = serde_json::to_string(transaction)?;
 
-        let record = FutureRecord::to(&self.transaction_topic)
-            .payload(&message)
-            .key(&transaction.signature);
+        let key = match self.key_strategy {
+            KafkaKeyStrategy::Signature => &transaction.signature,
+            // 旧记录没有命中地址，退回发送方
+            KafkaKeyStrategy::Address => transaction
+                .watched_address
+                .as_ref()
+                .unwrap_or(&transaction.from_address),
+        };
 
         match self
-            .producer
-            .send(record, rdkafka::util::Timeout::Never)
+            .deliver(self.topic_for(transaction), key, &message)
             .await
         {
             Ok(delivery) => {
                 info!("Transaction sent to Kafka: {:?}", delivery);
                 Ok(())
             }
-            Err((e, _)) => {
+            Err(e) => {
                 error!("Failed to send transaction to Kafka: {}", e);
-                Err(KafkaError::MessageProduction(RDKafkaErrorCode::Unknown).into())
+                metrics::record_kafka_send_failure();
+                Err(e)
             }
         }
     }
 
     pub async fn send_raw_message(&self, topic: &str, key: &str, payload: &str) -> Result<()> {
-        let record = FutureRecord::to(topic).payload(payload).key(key);
+        match self.deliver(topic, key, payload).await {
+            Ok(delivery) => {
+                info!("Message sent to Kafka topic {}: {:?}", topic, delivery);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to send message to Kafka topic {}: {}", topic, e);
+                Err(e)
+            }
+        }
+    }
+
+    /// 熔断打开时直接失败；否则在 send_timeout 内等待投递结果，返回 (partition, offset)
+    async fn deliver(&self, topic: &str, key: &str, payload: &str) -> Result<(i32, i64)> {
+        if !self.breaker.allow() {
+            return Err(anyhow!("Kafka circuit breaker is open"));
+        }
 
+        let record = FutureRecord::to(topic).payload(payload).key(key);
         match self
             .producer
-            .send(record, rdkafka::util::Timeout::Never)
+            .send(record, rdkafka::util::Timeout::After(self.send_timeout))
             .await
         {
             Ok(delivery) => {
-                info!("Message sent to Kafka topic {}: {:?}", topic, delivery);
-                Ok(())
+                self.breaker.record_success();
+                Ok(delivery)
             }
             Err((e, _)) => {
-                error!("Failed to send message to Kafka topic {}: {}", topic, e);
-                Err(KafkaError::MessageProduction(RDKafkaErrorCode::Unknown).into())
+                self.breaker.record_failure();
+                Err(e.into())
             }
         }
     }
+
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.state()
+    }
+
+    /// 按交易类型选择 topic，未单独配置时回退到 transaction_topic
+    pub fn topic_for(&self, transaction: &Transaction) -> &str {
+        let topic = match transaction.transaction_type {
+            TransactionType::Native => &self.native_topic,
+            TransactionType::Token => &self.token_topic,
+            TransactionType::Nft => &self.nft_topic,
+            TransactionType::Account | TransactionType::Unknown => &None,
+        };
+        topic.as_deref().unwrap_or(&self.transaction_topic)
+    }
+
+    /// 投递扫描器事件（如分叉回滚）到事件 topic
+    pub async fn send_event<T: Serialize>(&self, key: &str, event: &T) -> Result<()> {
+        let payload = serde_json::to_string(event)?;
+        self.send_raw_message(&self.event_topic, key, &payload)
+            .await
+    }
+
+    /// 投递关注地址变更事件，以地址为 key 保证同一地址的事件有序
+    pub async fn send_address_event(&self, event: &AddressEvent) -> Result<()> {
+        let payload = serde_json::to_string(event)?;
+        self.send_raw_message(&self.address_events_topic, &event.address, &payload)
+            .await
+    }
+
+    /// 拉取集群元数据以确认 broker 可达，阻塞调用
+    pub fn ping(&self, timeout: Duration) -> Result<()> {
+        self.producer
+            .client()
+            .fetch_metadata(None, rdkafka::util::Timeout::After(timeout))?;
+        Ok(())
+    }
+
+    /// 阻塞等待队列中的消息全部发送，退出前调用
+    pub fn flush(&self, timeout: Duration) -> Result<()> {
+        self.producer
+            .flush(rdkafka::util::Timeout::After(timeout))?;
+        Ok(())
+    }
+}
+
+/// 连续失败达到阈值后熔断，冷却期内不再尝试发送；冷却结束后放行请求试探，
+/// 成功则恢复，失败则重新熔断
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: AtomicU32,
+    opened_at: Mutex<Option<Instant>>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            consecutive_failures: AtomicU32::new(0),
+            opened_at: Mutex::new(None),
+        }
+    }
+
+    fn state(&self) -> CircuitState {
+        match *self.opened_at.lock().unwrap() {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    fn allow(&self) -> bool {
+        self.state() != CircuitState::Open
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        let mut opened_at = self.opened_at.lock().unwrap();
+        if opened_at.take().is_some() {
+            info!("Kafka circuit breaker closed");
+            metrics::set_kafka_circuit_open(false);
+        }
+    }
+
+    fn record_failure(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < self.failure_threshold {
+            return;
+        }
+        // 首次达到阈值或半开状态下试探失败时（重新）熔断
+        let mut opened_at = self.opened_at.lock().unwrap();
+        if !matches!(*opened_at, Some(at) if at.elapsed() < self.cooldown) {
+            warn!(
+                "Kafka circuit breaker opened after {} consecutive failures, pausing sends for {:?}",
+                failures, self.cooldown
+            );
+            *opened_at = Some(Instant::now());
+            metrics::set_kafka_circuit_open(true);
+        }
+    }
 }
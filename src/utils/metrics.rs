@@ -0,0 +1,57 @@
+use ::metrics::{counter, gauge};
+use anyhow::Result;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+const BLOCKS_SCANNED_TOTAL: &str = "solana_scanner_blocks_scanned_total";
+const TRANSACTIONS_MATCHED_TOTAL: &str = "solana_scanner_transactions_matched_total";
+const SLOT_LAG: &str = "solana_scanner_slot_lag";
+const RPC_ERRORS_TOTAL: &str = "solana_scanner_rpc_errors_total";
+const SLOTS_SKIPPED_TOTAL: &str = "solana_scanner_slots_skipped_total";
+const KAFKA_SEND_FAILURES_TOTAL: &str = "solana_scanner_kafka_send_failures_total";
+const KAFKA_CIRCUIT_OPEN: &str = "solana_scanner_kafka_circuit_open";
+const WEBSOCKET_CONNECTIONS: &str = "solana_scanner_websocket_connections";
+const WEBSOCKET_SLOW_CLIENTS_DROPPED_TOTAL: &str =
+    "solana_scanner_websocket_slow_clients_dropped_total";
+
+/// 安装全局 Prometheus recorder，返回的 handle 用于渲染 /metrics
+pub fn install() -> Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new().install_recorder()?;
+    Ok(handle)
+}
+
+pub fn record_block_scanned(matched: u64) {
+    counter!(BLOCKS_SCANNED_TOTAL).increment(1);
+    counter!(TRANSACTIONS_MATCHED_TOTAL).increment(matched);
+}
+
+/// 链上最新 slot 与已扫描检查点之差
+pub fn set_slot_lag(lag: u64) {
+    gauge!(SLOT_LAG).set(lag as f64);
+}
+
+pub fn record_rpc_error() {
+    counter!(RPC_ERRORS_TOTAL).increment(1);
+}
+
+/// leader 未出块的 slot 数，不计入 RPC 错误
+pub fn record_slot_skipped() {
+    counter!(SLOTS_SKIPPED_TOTAL).increment(1);
+}
+
+pub fn record_kafka_send_failure() {
+    counter!(KAFKA_SEND_FAILURES_TOTAL).increment(1);
+}
+
+/// Kafka 熔断器是否处于打开状态（1 为打开）
+pub fn set_kafka_circuit_open(open: bool) {
+    gauge!(KAFKA_CIRCUIT_OPEN).set(if open { 1.0 } else { 0.0 });
+}
+
+pub fn set_websocket_connections(count: usize) {
+    gauge!(WEBSOCKET_CONNECTIONS).set(count as f64);
+}
+
+/// 因发送队列已满被断开的 WebSocket 连接数
+pub fn record_websocket_slow_client_dropped() {
+    counter!(WEBSOCKET_SLOW_CLIENTS_DROPPED_TOTAL).increment(1);
+}
@@ -1,2 +1,5 @@
 pub mod error;
 pub mod kafka;
+pub mod metrics;
+pub mod retry;
+pub mod validation;
@@ -0,0 +1,61 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Solana RPC 服务端错误码
+const BLOCK_NOT_AVAILABLE: i64 = -32004;
+const NODE_UNHEALTHY: i64 = -32005;
+const SLOT_SKIPPED: i64 = -32007;
+const LONG_TERM_STORAGE_SLOT_SKIPPED: i64 = -32009;
+const BLOCK_STATUS_NOT_AVAILABLE_YET: i64 = -32014;
+
+const MAX_BACKOFF_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcErrorClass {
+    /// 超时、限流、区块暂不可用等，可以重试
+    Transient,
+    /// 该 slot 没有出块（leader 跳过），重试没有意义
+    SlotSkipped,
+    /// 其他错误，直接返回
+    Fatal,
+}
+
+pub fn classify_rpc_error(error: &ClientError) -> RpcErrorClass {
+    match error.kind() {
+        ClientErrorKind::Io(_) => RpcErrorClass::Transient,
+        ClientErrorKind::Reqwest(e) => {
+            let retryable_status = e
+                .status()
+                .map(|s| s.as_u16() == 429 || s.is_server_error())
+                .unwrap_or(false);
+            if e.is_timeout() || e.is_connect() || retryable_status {
+                RpcErrorClass::Transient
+            } else {
+                RpcErrorClass::Fatal
+            }
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => match *code {
+            SLOT_SKIPPED | LONG_TERM_STORAGE_SLOT_SKIPPED => RpcErrorClass::SlotSkipped,
+            BLOCK_NOT_AVAILABLE | NODE_UNHEALTHY | BLOCK_STATUS_NOT_AVAILABLE_YET => {
+                RpcErrorClass::Transient
+            }
+            _ => RpcErrorClass::Fatal,
+        },
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => RpcErrorClass::Transient,
+        _ => RpcErrorClass::Fatal,
+    }
+}
+
+/// 第 attempt 次重试前的等待时间：base * 2^attempt，再叠加最多一半的随机抖动
+pub fn backoff_delay(base_delay_ms: u64, attempt: u32) -> Duration {
+    let exp = base_delay_ms
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(MAX_BACKOFF_MS);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    let jitter = nanos % (exp / 2 + 1);
+    Duration::from_millis(exp + jitter)
+}
@@ -0,0 +1,9 @@
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::utils::error::{ScannerError, ScannerResult};
+
+/// 校验地址是否为合法的 base58 编码 32 字节公钥
+pub fn validate_address(address: &str) -> ScannerResult<Pubkey> {
+    Pubkey::from_str(address).map_err(|_| ScannerError::InvalidAddress(address.to_string()))
+}